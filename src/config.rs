@@ -1,4 +1,6 @@
 //! Configuration types
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 
 /// Normal or dryrun (read-only) mode.
 pub enum RunMode {
@@ -6,6 +8,70 @@ pub enum RunMode {
     DryRun,
 }
 
+/// Whether to stop at the first error or to collect all errors.
+pub enum ErrorMode {
+    /// Stop at the first error.
+    FailFast,
+    /// Continue past failing items and report all errors at the end.
+    KeepGoing,
+}
+
+/// The kind of output to write.
+#[derive(PartialEq, Eq)]
+pub enum OutputFormat {
+    /// HTML pages with images and thumbnails.
+    Html,
+    /// The gallery model as `gallery.json` with images and thumbnails.
+    Json,
+}
+
+/// How the overview shows image groups.
+#[derive(PartialEq, Eq)]
+pub enum OverviewLayout {
+    /// One card per image.
+    Images,
+    /// One card per image group showing its cover image and linking to the image group page.
+    /// Every image group gets a page, even without a markdown file.
+    Covers,
+}
+
+/// How to arrange the images on the overview and on image group pages without markdown files.
+#[derive(PartialEq, Eq)]
+pub enum ImageLayout {
+    /// A grid of images cropped to the same aspect ratio.
+    Grid,
+    /// Rows of equal height filling the page width, keeping the aspect ratio of every image.
+    Justified,
+    /// Columns of equal width, keeping the aspect ratio of every image.
+    Masonry,
+}
+
+/// What to do with images that are not referenced in the markdown file of their image group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UnreferencedImages {
+    /// Fail to render the image group.
+    Error,
+    /// Leave the images out of the image group page and report them, see
+    /// [`crate::Diagnostics::unreferenced`].
+    Warn,
+    /// Show the images in a grid after the markdown content.
+    Append,
+}
+
+/// How to show the dates of image groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateFormat {
+    /// Numeric dates in the customary order of the language, for example `02.01.2021` in
+    /// German. Languages without a translation of the date formats, including English, use
+    /// ISO 8601.
+    Numeric,
+    /// ISO 8601 dates like `2021-01-02` in all languages.
+    Iso,
+    /// Dates with the month name in the language of the page, for example `2 January 2021`.
+    /// Languages without translated month names use the English ones.
+    Long,
+}
+
 /// Image group order.
 pub enum GalleryOrder {
     MostRecentFirst,
@@ -13,15 +79,172 @@ pub enum GalleryOrder {
 }
 
 /// Configuration options for the output module.
+///
+/// Use [`Config::builder`] to create a configuration with default values. New options may be
+/// added in any release, so a configuration can't be created with a struct literal.
+#[non_exhaustive]
 pub struct Config {
     /// The target directory where to write the gallery.
-    pub output_path: std::path::PathBuf,
+    pub output_path: PathBuf,
     /// Normal or dryrun (read-only) mode.
     pub run_mode: RunMode,
+    /// Whether to stop at the first error or to collect all errors.
+    pub error_mode: ErrorMode,
+    /// The kind of output to write.
+    pub format: OutputFormat,
+    /// How the overview shows image groups.
+    pub overview_layout: OverviewLayout,
+    /// How to arrange the images.
+    pub image_layout: ImageLayout,
     /// Image group order.
     pub order: GalleryOrder,
     /// The top-level title of the generated gallery.
     pub page_title: String,
     /// An optional footer to show (for example) a copyright notice.
     pub page_footer: Option<String>,
+    /// The number of image groups per overview and archive page.
+    /// [`None`] shows all image groups on a single page.
+    pub page_size: Option<NonZeroUsize>,
+    /// Whether to write an archive page per month in addition to the archive page per year.
+    pub month_archives: bool,
+    /// Whether to enable the markdown extensions tables, footnotes, strikethrough, task lists,
+    /// and custom heading IDs in addition to plain CommonMark.
+    pub markdown_extensions: bool,
+    /// What to do with images missing from the markdown file of their image group.
+    /// Image groups can override this in their front matter.
+    pub unreferenced_images: UnreferencedImages,
+    /// The language codes of the gallery, for example `en` and `de`.
+    /// The first language is the default and its pages are written to the output directory,
+    /// the pages of every other language to a subdirectory named after the language.
+    /// An empty list writes a single gallery in English.
+    pub languages: Vec<String>,
+    /// How to show the dates of image groups. Templates can format dates differently with the
+    /// `format_date` helper.
+    ///
+    /// The patterns of the formats and the month names depend on the language and can be
+    /// customized in the translation files `<theme>/translations/<lang>.json` of [`Self::theme`].
+    pub date_format: DateFormat,
+    /// An optional directory with handlebars templates and partials that replace the built-in
+    /// ones, for example `overview.handlebars` and `partials/footer.handlebars`.
+    pub theme: Option<PathBuf>,
+}
+
+/// Creates a [`Config`] step by step, starting from default values.
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl Config {
+    /// Starts a configuration that writes the gallery to `output_path`.
+    ///
+    /// By default, the output is HTML with one overview card per image in a grid, the most recent
+    /// image groups come first,
+    /// errors stop the run, the gallery has no title and no footer,
+    /// the overview is a single page, there are only yearly archive pages, and markdown extensions
+    /// are enabled. Markdown files must reference all images of their image group.
+    /// The gallery has a single language, shows numeric dates, and uses the built-in templates.
+    pub fn builder(output_path: impl Into<PathBuf>) -> ConfigBuilder {
+        ConfigBuilder {
+            config: Config {
+                output_path: output_path.into(),
+                run_mode: RunMode::Normal,
+                error_mode: ErrorMode::FailFast,
+                format: OutputFormat::Html,
+                overview_layout: OverviewLayout::Images,
+                image_layout: ImageLayout::Grid,
+                order: GalleryOrder::MostRecentFirst,
+                page_title: String::new(),
+                page_footer: None,
+                page_size: None,
+                month_archives: false,
+                markdown_extensions: true,
+                unreferenced_images: UnreferencedImages::Error,
+                languages: Vec::new(),
+                date_format: DateFormat::Numeric,
+                theme: None,
+            },
+        }
+    }
+}
+
+impl ConfigBuilder {
+    /// Sets normal or dryrun (read-only) mode.
+    pub fn run_mode(mut self, run_mode: RunMode) -> Self {
+        self.config.run_mode = run_mode;
+        self
+    }
+    /// Sets whether to stop at the first error or to collect all errors.
+    pub fn error_mode(mut self, error_mode: ErrorMode) -> Self {
+        self.config.error_mode = error_mode;
+        self
+    }
+    /// Sets the kind of output to write.
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.config.format = format;
+        self
+    }
+    /// Sets how the overview shows image groups.
+    pub fn overview_layout(mut self, overview_layout: OverviewLayout) -> Self {
+        self.config.overview_layout = overview_layout;
+        self
+    }
+    /// Sets how to arrange the images.
+    pub fn image_layout(mut self, image_layout: ImageLayout) -> Self {
+        self.config.image_layout = image_layout;
+        self
+    }
+    /// Sets the image group order.
+    pub fn order(mut self, order: GalleryOrder) -> Self {
+        self.config.order = order;
+        self
+    }
+    /// Sets the top-level title of the generated gallery.
+    pub fn page_title(mut self, page_title: impl Into<String>) -> Self {
+        self.config.page_title = page_title.into();
+        self
+    }
+    /// Sets an HTML snippet for the page footer.
+    pub fn page_footer(mut self, page_footer: Option<String>) -> Self {
+        self.config.page_footer = page_footer;
+        self
+    }
+    /// Sets the number of image groups per overview and archive page.
+    pub fn page_size(mut self, page_size: Option<NonZeroUsize>) -> Self {
+        self.config.page_size = page_size;
+        self
+    }
+    /// Sets whether to write an archive page per month.
+    pub fn month_archives(mut self, month_archives: bool) -> Self {
+        self.config.month_archives = month_archives;
+        self
+    }
+    /// Sets whether to enable markdown extensions beyond plain CommonMark.
+    pub fn markdown_extensions(mut self, markdown_extensions: bool) -> Self {
+        self.config.markdown_extensions = markdown_extensions;
+        self
+    }
+    /// Sets what to do with images missing from the markdown file of their image group.
+    pub fn unreferenced_images(mut self, unreferenced_images: UnreferencedImages) -> Self {
+        self.config.unreferenced_images = unreferenced_images;
+        self
+    }
+    /// Sets the language codes of the gallery, starting with the default language.
+    pub fn languages(mut self, languages: Vec<String>) -> Self {
+        self.config.languages = languages;
+        self
+    }
+    /// Sets how to show the dates of image groups.
+    pub fn date_format(mut self, date_format: DateFormat) -> Self {
+        self.config.date_format = date_format;
+        self
+    }
+    /// Sets a directory with templates that replace the built-in templates.
+    pub fn theme(mut self, theme: Option<PathBuf>) -> Self {
+        self.config.theme = theme;
+        self
+    }
+    /// Returns the finished configuration.
+    pub fn build(self) -> Config {
+        self.config
+    }
 }
@@ -1,10 +1,215 @@
-//! Error helpers.
-use anyhow::anyhow;
-use std::path::Path;
+//! Error types and helpers.
+use crate::input::Skipped;
+use crate::output::Category;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// An error of the build pipeline.
+///
+/// Well-known failures have their own variants. Everything else is an [`Error::Input`] or
+/// [`Error::Output`] error, depending on the stage that failed.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input directory does not exist or is not a directory.
+    MissingInputDirectory { path: PathBuf },
+    /// A group directory name starts with an invalid date.
+    InvalidGroupDirectoryName { path: PathBuf, message: String },
+    /// Input entries were skipped in strict mode.
+    SkippedInputEntries { skipped: Vec<Skipped> },
+    /// A markdown file references images that don't exist.
+    UnknownMarkdownImages { path: PathBuf, images: Vec<String> },
+    /// Images exist on disk but are not referenced in the markdown file.
+    MissingMarkdownImages { path: PathBuf, images: Vec<String> },
+    /// Imagemagick failed to convert an image.
+    ImageConversion { path: PathBuf, stderr: String },
+    /// Writing a file or directory failed.
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// All errors of a run that continued past errors, see [`crate::ErrorMode::KeepGoing`].
+    Collected(Vec<(Category, Error)>),
+    /// Any other failure while reading the source directory.
+    Input(anyhow::Error),
+    /// Any other failure while writing the gallery.
+    Output(anyhow::Error),
+}
+
+/// The stage of the build pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Reading the source directory.
+    Input,
+    /// Writing the gallery.
+    Output,
+}
+
+impl Error {
+    /// The stage of the build pipeline that failed.
+    pub fn stage(&self) -> Stage {
+        match self {
+            Error::MissingInputDirectory { .. }
+            | Error::InvalidGroupDirectoryName { .. }
+            | Error::SkippedInputEntries { .. }
+            | Error::Input(_) => Stage::Input,
+            _ => Stage::Output,
+        }
+    }
+
+    /// The offending file or directory, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::MissingInputDirectory { path }
+            | Error::InvalidGroupDirectoryName { path, .. }
+            | Error::UnknownMarkdownImages { path, .. }
+            | Error::MissingMarkdownImages { path, .. }
+            | Error::ImageConversion { path, .. }
+            | Error::Write { path, .. } => Some(path),
+            Error::SkippedInputEntries { .. } | Error::Collected(_) => None,
+            Error::Input(e) | Error::Output(e) => error_path(e),
+        }
+    }
+
+    /// Converts an internal error into an [`Error`], keeping well-known errors intact.
+    pub(crate) fn from_anyhow(e: anyhow::Error, stage: Stage) -> Error {
+        match e.downcast::<Error>() {
+            Ok(e) => e,
+            Err(e) => match stage {
+                Stage::Input => Error::Input(e),
+                Stage::Output => Error::Output(e),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::MissingInputDirectory { path } => {
+                write!(
+                    f,
+                    "Input directory not found: \"{}\"",
+                    path.to_string_lossy()
+                )
+            }
+            Error::InvalidGroupDirectoryName { path, message } => {
+                write!(f, "{}: \"{}\"", message, path.to_string_lossy())
+            }
+            Error::SkippedInputEntries { skipped } => {
+                write!(f, "Skipped {} input entries in strict mode:", skipped.len())?;
+                for s in skipped {
+                    write!(f, "\n{}", s)?;
+                }
+                Ok(())
+            }
+            Error::UnknownMarkdownImages { path, images } => write!(
+                f,
+                "Unknown images in markdown file \"{}\": {}",
+                path.to_string_lossy(),
+                quoted_list(images)
+            ),
+            Error::MissingMarkdownImages { path, images } => write!(
+                f,
+                "Images present on disk but missing from the markdown file \"{}\": {}",
+                path.to_string_lossy(),
+                quoted_list(images)
+            ),
+            Error::ImageConversion { path, stderr } => write!(
+                f,
+                "Failed to convert image: \"{}\"\nstderr:\n{}",
+                path.to_string_lossy(),
+                stderr
+            ),
+            Error::Write { path, source } => {
+                write!(f, "Failed to write \"{}\"", path.to_string_lossy())?;
+                // Like anyhow, the alternate form includes the source.
+                if f.alternate() {
+                    write!(f, ": {}", source)?;
+                }
+                Ok(())
+            }
+            Error::Collected(errors) => {
+                write!(f, "{} errors:", errors.len())?;
+                let mut current = None;
+                for (category, e) in errors {
+                    if current != Some(category) {
+                        write!(f, "\n\n{}:", category)?;
+                        current = Some(category);
+                    }
+                    write!(f, "\n  * {}", format!("{:#}", e).replace('\n', "\n    "))?;
+                }
+                Ok(())
+            }
+            Error::Input(e) | Error::Output(e) => {
+                if f.alternate() {
+                    write!(f, "{:#}", e)
+                } else {
+                    write!(f, "{}", e)
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Write { source, .. } => Some(source),
+            Error::Input(e) | Error::Output(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+/// Formats a list of names as a comma-separated list of quoted names.
+fn quoted_list(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| format!("\"{}\"", name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// An error about a specific file or directory.
+///
+/// Errors created with [`path_error`] or [`PathErrorContext::path_context`] carry this type,
+/// so the offending path can be recovered with [`error_path`].
+#[derive(Debug)]
+pub struct PathError {
+    /// The error message without the path.
+    pub message: String,
+    /// The offending file or directory.
+    pub path: PathBuf,
+    /// Whether to quote the path in the human-readable message.
+    quoted: bool,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.quoted {
+            write!(f, "{}: \"{}\"", self.message, self.path.to_string_lossy())
+        } else {
+            write!(f, "{}: {}", self.message, self.path.to_string_lossy())
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
 
 /// Creates a new error with the given message and path.
 pub fn path_error(msg: &str, path: &Path) -> anyhow::Error {
-    anyhow!("{}: {}", msg, path.to_string_lossy())
+    anyhow::Error::new(PathError {
+        message: msg.to_owned(),
+        path: path.to_owned(),
+        quoted: false,
+    })
+}
+
+/// Returns the path of the outermost [`PathError`] in the error chain, if any.
+pub fn error_path(e: &anyhow::Error) -> Option<&Path> {
+    e.downcast_ref::<PathError>().map(|e| e.path.as_path())
 }
 
 pub trait PathErrorContext<T, E> {
@@ -17,6 +222,88 @@ where
     C: anyhow::Context<T, E>,
 {
     fn path_context(self, msg: &str, path: &Path) -> anyhow::Result<T> {
-        self.with_context(|| format!("{}: \"{}\"", msg, path.to_string_lossy()))
+        self.with_context(|| PathError {
+            message: msg.to_owned(),
+            path: path.to_owned(),
+            quoted: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, PathErrorContext, Stage, error_path, path_error};
+    use anyhow::Context;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn path_error_message() {
+        let e = path_error("Something failed", Path::new("some/file"));
+        assert_eq!(e.to_string(), "Something failed: some/file");
+        assert_eq!(error_path(&e), Some(Path::new("some/file")));
+    }
+
+    #[test]
+    fn path_context_message() {
+        let e = Err::<(), _>(std::fmt::Error)
+            .path_context("Something failed", Path::new("some/file"))
+            .unwrap_err();
+        assert_eq!(e.to_string(), "Something failed: \"some/file\"");
+        assert_eq!(error_path(&e), Some(Path::new("some/file")));
+    }
+
+    #[test]
+    fn error_path_through_context() {
+        let e = Err::<(), _>(path_error("Inner", Path::new("inner")))
+            .path_context("Outer", Path::new("outer"))
+            .context("Without path")
+            .unwrap_err();
+        assert_eq!(error_path(&e), Some(Path::new("outer")));
+    }
+
+    #[test]
+    fn error_path_missing() {
+        assert_eq!(error_path(&anyhow::anyhow!("No path")), None);
+    }
+
+    #[test]
+    fn from_anyhow_keeps_typed_errors() {
+        let e = anyhow::Error::new(Error::UnknownMarkdownImages {
+            path: PathBuf::from("index.md"),
+            images: vec!["A".to_owned(), "B".to_owned()],
+        })
+        .context("Failed to write gallery");
+        let e = Error::from_anyhow(e, Stage::Output);
+        assert!(matches!(e, Error::UnknownMarkdownImages { .. }));
+        assert_eq!(e.path(), Some(Path::new("index.md")));
+        assert_eq!(
+            e.to_string(),
+            "Unknown images in markdown file \"index.md\": \"A\", \"B\""
+        );
+    }
+
+    #[test]
+    fn write_error_source() {
+        let e = Error::Write {
+            path: PathBuf::from("out"),
+            source: std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+        };
+        assert_eq!(e.to_string(), "Failed to write \"out\"");
+        assert_eq!(
+            format!("{:#}", e),
+            "Failed to write \"out\": permission denied"
+        );
+        assert_eq!(
+            std::error::Error::source(&e).map(|s| s.to_string()),
+            Some("permission denied".to_owned())
+        );
+    }
+
+    #[test]
+    fn from_anyhow_wraps_other_errors() {
+        let e = Error::from_anyhow(path_error("Failed", Path::new("file")), Stage::Input);
+        assert!(matches!(e, Error::Input(_)));
+        assert_eq!(e.stage(), Stage::Input);
+        assert_eq!(e.path(), Some(Path::new("file")));
     }
 }
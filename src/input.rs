@@ -1,24 +1,257 @@
 //! Reads the source directory into the internal representation.
 //!
 //! This is a read-only operation.
-use crate::error::PathErrorContext;
-use crate::model::{Gallery, Image, ImageGroup};
+mod metadata;
+
+pub(crate) use metadata::split_front_matter;
+
+use crate::error::{Error, PathErrorContext};
+use crate::model::{DateRange, Gallery, Image, ImageGroup, Page, natural_cmp};
 
 use anyhow::Result;
 use regex::Regex;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::{fmt, fs};
-use time::{Date, macros::format_description};
+use time::{Date, Month, macros::format_description};
+
+/// The directory in the source directory with the markdown files of standalone pages.
+const PAGES_DIR: &str = "pages";
 
-pub fn gallery_from_dir(path: &Path) -> Result<Gallery> {
+/// Reads the source directory.
+///
+/// Entries that don't fit into the gallery structure are skipped and recorded in `diagnostics`.
+/// Markdown files like `index.de.md` are translations if their language is one of `languages`
+/// other than the first, the default language.
+pub fn gallery_from_dir(
+    path: &Path,
+    languages: &[String],
+    diagnostics: &mut Diagnostics,
+) -> Result<Gallery> {
+    if !path.is_dir() {
+        return Err(Error::MissingInputDirectory {
+            path: path.to_owned(),
+        }
+        .into());
+    }
     let mut image_groups = Vec::<ImageGroup>::new();
-    for d in read_dir(path)?.iter().filter(|d| d.is_dir) {
+    let mut pages = Vec::new();
+    let mut index_file = None;
+    let mut index_translations = BTreeMap::new();
+    for d in read_dir(path)? {
+        if d.is_dir && d.file_name == Path::new(PAGES_DIR) {
+            pages = pages_from_entries(&read_dir(&d.path)?, languages, diagnostics)?;
+            continue;
+        }
+        if d.is_index() {
+            index_file = Some(d.path.clone());
+            continue;
+        }
+        if let Some(language) = d.index_translation(languages) {
+            index_translations.insert(language, d.path.clone());
+            continue;
+        }
+        if !d.is_dir {
+            diagnostics.skip(&d, SkipReason::NotADirectory);
+            continue;
+        }
         let contents = read_dir(&d.path)?;
-        if let Some(group) = ImageGroup::from_entries(&d.file_name, &contents)? {
-            image_groups.push(group);
+        match ImageGroup::from_entries(&d.file_name, &contents, languages, diagnostics)? {
+            Some(mut group) => {
+                group.read_metadata()?;
+                image_groups.push(group)
+            }
+            None => diagnostics.skip(&d, SkipReason::MissingDate),
+        }
+    }
+    let index_translations = diagnostics.orphaned(&index_file, index_translations);
+    Ok(Gallery {
+        image_groups,
+        pages,
+        index_file,
+        index_translations,
+    })
+}
+
+/// Reads the markdown files of the standalone pages.
+fn pages_from_entries(
+    v: &[DirEntry],
+    languages: &[String],
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<Page>> {
+    let mut pages = Vec::new();
+    let mut translations = Vec::new();
+    for d in v {
+        if d.is_dir {
+            diagnostics.skip(d, SkipReason::NestedDirectory);
+        } else if let Some((title, language)) = d.translation(languages) {
+            translations.push(((title, language), d));
+        } else if d.path.extension().is_some_and(|e| e == "md") {
+            pages.push(Page {
+                title: d
+                    .file_name
+                    .file_stem()
+                    .path_context("Failed to determine file stem", &d.path)?
+                    .to_str()
+                    .path_context("Failed to decode file name as UTF-8", &d.path)?
+                    .to_owned(),
+                markdown_file: d.path.clone(),
+                translations: BTreeMap::new(),
+            });
+        } else {
+            diagnostics.skip(d, SkipReason::NotAMarkdownFile);
+        }
+    }
+    pages.sort_by(|lhs, rhs| natural_cmp(&lhs.title, &rhs.title));
+    // Pages like `About.md` and `about.md` would overwrite each other, the first one wins.
+    let mut urls = HashSet::new();
+    pages.retain(|p| {
+        let unique = urls.insert(p.url());
+        if !unique {
+            diagnostics.skipped.push(Skipped {
+                path: p.markdown_file.clone(),
+                reason: SkipReason::DuplicatePageUrl,
+            });
+        }
+        unique
+    });
+    for ((title, language), d) in translations {
+        match pages.iter_mut().find(|p| p.title == title) {
+            Some(page) => {
+                page.translations.insert(language, d.path.clone());
+            }
+            None => diagnostics.skip(d, SkipReason::TranslationWithoutOriginal),
+        }
+    }
+    Ok(pages)
+}
+
+/// Collects problems that don't stop the build, such as input entries skipped while reading the
+/// source directory.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    /// The skipped entries in the order they were encountered.
+    pub skipped: Vec<Skipped>,
+    /// The images left out of image group pages, see
+    /// [`crate::config::UnreferencedImages::Warn`].
+    pub unreferenced: Vec<Unreferenced>,
+}
+
+/// Images that are not referenced in the markdown file of their image group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unreferenced {
+    /// The full path to the markdown file.
+    pub path: PathBuf,
+    /// The names of the unreferenced images.
+    pub images: Vec<String>,
+}
+
+/// An input entry that is not part of the gallery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    /// The full path to the skipped entry.
+    pub path: PathBuf,
+    /// Why the entry was skipped.
+    pub reason: SkipReason,
+}
+
+/// The reason why an input entry was skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// A file next to the image group directories.
+    NotADirectory,
+    /// A directory name without a date prefix.
+    MissingDate,
+    /// A directory inside an image group directory.
+    NestedDirectory,
+    /// A file in an image group directory that is neither an image, an XMP sidecar file, nor the markdown file.
+    UnknownFileType,
+    /// A file in the pages directory that is not a markdown file.
+    NotAMarkdownFile,
+    /// A translated markdown file like `index.de.md` without the markdown file it translates.
+    TranslationWithoutOriginal,
+    /// A markdown file in the pages directory whose page has the same URL as another page.
+    DuplicatePageUrl,
+}
+
+impl Diagnostics {
+    fn skip(&mut self, d: &DirEntry, reason: SkipReason) {
+        self.skipped.push(Skipped {
+            path: d.path.clone(),
+            reason,
+        });
+    }
+
+    /// Skips the translations if the markdown file they translate doesn't exist.
+    fn orphaned(
+        &mut self,
+        original: &Option<PathBuf>,
+        translations: BTreeMap<String, PathBuf>,
+    ) -> BTreeMap<String, PathBuf> {
+        if original.is_some() {
+            return translations;
         }
+        for path in translations.into_values() {
+            self.skipped.push(Skipped {
+                path,
+                reason: SkipReason::TranslationWithoutOriginal,
+            });
+        }
+        BTreeMap::new()
+    }
+
+    /// Returns an error listing all skipped entries in strict mode.
+    ///
+    /// Outside of strict mode, skipped entries are only warnings and this always succeeds.
+    pub fn check(&self, strict: bool) -> Result<(), Error> {
+        if strict && !self.skipped.is_empty() {
+            return Err(Error::SkippedInputEntries {
+                skipped: self.skipped.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Skipped \"{}\": {}",
+            self.path.to_string_lossy(),
+            self.reason
+        )
+    }
+}
+
+impl fmt::Display for Unreferenced {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Images not referenced in the markdown file \"{}\": {}",
+            self.path.to_string_lossy(),
+            self.images
+                .iter()
+                .map(|i| format!("\"{}\"", i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            SkipReason::NotADirectory => "not an image group directory",
+            SkipReason::MissingDate => "directory name does not start with a date",
+            SkipReason::NestedDirectory => "nested directories are not supported",
+            SkipReason::UnknownFileType => "neither an image, an XMP file, nor index.md",
+            SkipReason::NotAMarkdownFile => "not a markdown file",
+            SkipReason::TranslationWithoutOriginal => "translation without original markdown file",
+            SkipReason::DuplicatePageUrl => "another page has the same URL",
+        })
     }
-    Ok(Gallery { image_groups })
 }
 
 impl Image {
@@ -28,40 +261,115 @@ impl Image {
 }
 
 impl ImageGroup {
-    fn from_entries(path: &Path, v: &[DirEntry]) -> Result<Option<ImageGroup>> {
-        let (title, date) = {
-            let id = path.to_str().unwrap_or("");
-            let re = Regex::new(r"^(\d{4}-\d{2}-\d{2}).").unwrap();
-            let Some(c) = re.captures(id) else {
-                return Ok(None);
-            };
-            (
-                re.replace(id, "").into_owned(),
-                Date::parse(
-                    c.get(1).unwrap().as_str(),
-                    format_description!("[year]-[month]-[day]"),
-                )?,
-            )
+    fn from_entries(
+        path: &Path,
+        v: &[DirEntry],
+        languages: &[String],
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Option<ImageGroup>> {
+        let Some((title, date)) = parse_group_dir_name(path)? else {
+            return Ok(None);
         };
         let mut images = Vec::new();
         let mut markdown_file = None;
+        let mut translations = BTreeMap::new();
         for d in v {
-            if d.is_image() {
+            if d.is_dir {
+                diagnostics.skip(d, SkipReason::NestedDirectory);
+            } else if d.is_image() {
                 images.push(Image::from(d)?);
-            }
-            if d.is_index() {
+            } else if d.is_index() {
                 markdown_file = Some(d.path.clone());
+            } else if let Some(language) = d.index_translation(languages) {
+                translations.insert(language, d.path.clone());
+            } else if metadata::is_sidecar(&d.path) {
+                // Read together with the image, see `read_metadata`.
+            } else {
+                diagnostics.skip(d, SkipReason::UnknownFileType);
             }
         }
         images.sort();
+        let translations = diagnostics.orphaned(&markdown_file, translations);
         Ok(Some(ImageGroup {
             path: path.to_owned(),
             title,
             date,
             images,
             markdown_file,
+            tags: Vec::new(),
+            cover: None,
+            unreferenced_images: None,
+            titles: BTreeMap::new(),
+            translations,
         }))
     }
+
+    /// Reads the metadata of the image group from the front matter of its markdown file
+    /// and the tags of its images from their XMP sidecar files.
+    fn read_metadata(&mut self) -> Result<()> {
+        if let Some(markdown_file) = &self.markdown_file {
+            let front_matter = metadata::read_front_matter(markdown_file)?;
+            self.tags = front_matter.tags;
+            self.cover = front_matter.cover;
+            self.unreferenced_images = front_matter.unreferenced_images;
+            self.titles = front_matter.titles;
+        }
+        for image in &mut self.images {
+            let sidecar = metadata::sidecar_paths(&image.path)
+                .into_iter()
+                .find(|p| p.is_file());
+            if let Some(sidecar) = sidecar {
+                image.tags = metadata::read_xmp_tags(&sidecar)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits a group directory name into its title and date.
+///
+/// The directory name must start with one of the following date prefixes,
+/// followed by a separator and the title:
+/// * `YYYY` for a whole year,
+/// * `YYYY-MM` for a whole month,
+/// * `YYYY-MM-DD` for a single day,
+/// * `YYYY-MM-DD..YYYY-MM-DD` for a range of days.
+///
+/// Returns [`None`] if there is no date prefix.
+fn parse_group_dir_name(path: &Path) -> Result<Option<(String, DateRange)>> {
+    let id = path.to_str().unwrap_or("");
+    let re = Regex::new(
+        r"^(?<year>\d{4})(?:-(?<month>\d{2})(?:-(?<day>\d{2})(?:\.\.(?<end>\d{4}-\d{2}-\d{2}))?)?)?[^\d]",
+    )
+    .unwrap();
+    let Some(c) = re.captures(id) else {
+        return Ok(None);
+    };
+    let invalid = |message: &str| Error::InvalidGroupDirectoryName {
+        path: path.to_owned(),
+        message: message.to_owned(),
+    };
+    let title = re.replace(id, "").into_owned();
+    let year = c["year"].parse()?;
+    let Some(month) = c.name("month") else {
+        return Ok(Some((title, DateRange::Year(year))));
+    };
+    let month = Month::try_from(month.as_str().parse::<u8>()?)
+        .map_err(|_| invalid("Invalid month in directory name"))?;
+    let Some(day) = c.name("day") else {
+        return Ok(Some((title, DateRange::Month(year, month))));
+    };
+    let first = Date::from_calendar_date(year, month, day.as_str().parse()?)
+        .map_err(|_| invalid("Invalid date in directory name"))?;
+    let Some(end) = c.name("end") else {
+        return Ok(Some((title, DateRange::Day(first))));
+    };
+    let last = Date::parse(end.as_str(), format_description!("[year]-[month]-[day]"))
+        .map_err(|_| invalid("Invalid end date in directory name"))?;
+    if last < first {
+        return Err(invalid("End date before start date in directory name").into());
+    }
+    Ok(Some((title, DateRange::Days(first, last))))
 }
 
 #[derive(Debug)]
@@ -80,6 +388,26 @@ impl DirEntry {
     fn is_index(&self) -> bool {
         self.path.file_name().is_some_and(|f| f == "index.md")
     }
+    /// Splits a translated markdown file name like `About.de.md` into the stem and the language.
+    ///
+    /// Only the `languages` after the first, the default language, have translations. Other
+    /// file names like `Notes.old.md` are not translations.
+    fn translation(&self, languages: &[String]) -> Option<(String, String)> {
+        let re = Regex::new(r"^(.+)\.([a-z]{2,3}(?:-[A-Za-z0-9]+)?)\.md$").unwrap();
+        let c = re.captures(self.path.file_name()?.to_str()?)?;
+        let language = c[2].to_owned();
+        languages
+            .iter()
+            .skip(1)
+            .any(|l| *l == language)
+            .then(|| (c[1].to_owned(), language))
+    }
+    /// The language of a translated markdown file like `index.de.md`, see [`Self::translation`].
+    fn index_translation(&self, languages: &[String]) -> Option<String> {
+        self.translation(languages)
+            .filter(|(stem, _)| stem == "index")
+            .map(|(_, language)| language)
+    }
 }
 
 impl fmt::Display for DirEntry {
@@ -111,7 +439,11 @@ fn read_dir(base_dir: &Path) -> Result<Vec<DirEntry>> {
 
 #[cfg(test)]
 mod tests {
-    use super::{DirEntry, Image, ImageGroup};
+    use super::{
+        Diagnostics, DirEntry, Image, ImageGroup, SkipReason, Skipped, pages_from_entries,
+    };
+    use crate::model::DateRange;
+    use std::collections::BTreeMap;
     use std::path::{Path, PathBuf};
     use time::{Date, Month};
 
@@ -128,7 +460,7 @@ mod tests {
     struct SimpleImageGroup<'a> {
         name: &'a str,
         title: &'a str,
-        date: Date,
+        date: DateRange,
         // (name, path)
         images: &'a [(&'a str, &'a str)],
         markdown_file: Option<&'a str>,
@@ -146,9 +478,15 @@ mod tests {
                         name: String::from(*n),
                         path: PathBuf::from(p),
                         file_name: PathBuf::from(p).file_name().unwrap().into(),
+                        tags: Vec::new(),
                     })
                     .collect(),
                 markdown_file: s.markdown_file.map(PathBuf::from),
+                tags: Vec::new(),
+                cover: None,
+                unreferenced_images: None,
+                titles: BTreeMap::new(),
+                translations: BTreeMap::new(),
             }
         }
     }
@@ -156,11 +494,17 @@ mod tests {
     #[test]
     fn test_empty_dir() {
         assert_eq!(
-            ImageGroup::from_entries(Path::new("2021-01-01 Fuji, Japan"), &[]).unwrap(),
+            ImageGroup::from_entries(
+                Path::new("2021-01-01 Fuji, Japan"),
+                &[],
+                &[],
+                &mut Diagnostics::default()
+            )
+            .unwrap(),
             Some(ImageGroup::from(SimpleImageGroup {
                 title: "Fuji, Japan",
                 name: "2021-01-01 Fuji, Japan",
-                date: Date::from_calendar_date(2021, Month::January, 01).unwrap(),
+                date: DateRange::Day(Date::from_calendar_date(2021, Month::January, 1).unwrap()),
                 images: &[],
                 markdown_file: None,
             }))
@@ -174,13 +518,15 @@ mod tests {
                 &dir(
                     "2021-01-01 Fuji, Japan",
                     &[("Valley.webp", false), ("Summit.webp", false),]
-                )
+                ),
+                &[],
+                &mut Diagnostics::default()
             )
             .unwrap(),
             Some(ImageGroup::from(SimpleImageGroup {
                 name: "2021-01-01 Fuji, Japan",
                 title: "Fuji, Japan",
-                date: Date::from_calendar_date(2021, Month::January, 01).unwrap(),
+                date: DateRange::Day(Date::from_calendar_date(2021, Month::January, 1).unwrap()),
                 images: &[
                     ("Summit", "2021-01-01 Fuji, Japan/Summit.webp"),
                     ("Valley", "2021-01-01 Fuji, Japan/Valley.webp"),
@@ -194,20 +540,82 @@ mod tests {
         assert_eq!(
             ImageGroup::from_entries(
                 Path::new("2021-01-01 Fuji, Japan"),
-                &dir("some/path/2021-01-01 Fuji, Japan", &[("index.md", false)])
+                &dir("some/path/2021-01-01 Fuji, Japan", &[("index.md", false)]),
+                &[],
+                &mut Diagnostics::default()
             )
             .unwrap(),
             Some(ImageGroup::from(SimpleImageGroup {
                 name: "2021-01-01 Fuji, Japan",
                 title: "Fuji, Japan",
-                date: Date::from_calendar_date(2021, Month::January, 01).unwrap(),
+                date: DateRange::Day(Date::from_calendar_date(2021, Month::January, 1).unwrap()),
                 images: &[],
                 markdown_file: Some("some/path/2021-01-01 Fuji, Japan/index.md")
             }))
         );
     }
     #[test]
+    fn test_translations() {
+        let languages = ["en".to_owned(), "de".to_owned(), "pt-BR".to_owned()];
+        let mut diagnostics = Diagnostics::default();
+        let group = ImageGroup::from_entries(
+            Path::new("2021-01-01 Fuji, Japan"),
+            &dir(
+                "some/path/2021-01-01 Fuji, Japan",
+                &[
+                    ("index.md", false),
+                    ("index.de.md", false),
+                    ("index.bak.md", false),
+                    ("index.en.md", false),
+                ],
+            ),
+            &languages,
+            &mut diagnostics,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            group.translations,
+            BTreeMap::from([(
+                "de".to_owned(),
+                PathBuf::from("some/path/2021-01-01 Fuji, Japan/index.de.md")
+            )])
+        );
+        // Translations need the original markdown file.
+        let group = ImageGroup::from_entries(
+            Path::new("2021-01-01 Fuji, Japan"),
+            &dir(
+                "some/path/2021-01-01 Fuji, Japan",
+                &[("index.pt-BR.md", false)],
+            ),
+            &languages,
+            &mut diagnostics,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(group.translations.is_empty());
+        assert_eq!(
+            diagnostics.skipped,
+            vec![
+                // Only the configured languages count as translations.
+                Skipped {
+                    path: PathBuf::from("some/path/2021-01-01 Fuji, Japan/index.bak.md"),
+                    reason: SkipReason::UnknownFileType,
+                },
+                Skipped {
+                    path: PathBuf::from("some/path/2021-01-01 Fuji, Japan/index.en.md"),
+                    reason: SkipReason::UnknownFileType,
+                },
+                Skipped {
+                    path: PathBuf::from("some/path/2021-01-01 Fuji, Japan/index.pt-BR.md"),
+                    reason: SkipReason::TranslationWithoutOriginal,
+                },
+            ]
+        );
+    }
+    #[test]
     fn test_ignored_entries() {
+        let mut diagnostics = Diagnostics::default();
         assert_eq!(
             ImageGroup::from_entries(
                 Path::new("2021-12-01 Fuji, Japan"),
@@ -216,29 +624,190 @@ mod tests {
                     &[
                         ("Valley", true), // directory
                         ("Summit.webp", false),
+                        ("Summit.webp.xmp", false),
                         ("something.unknown", false),
                     ]
-                )
+                ),
+                &[],
+                &mut diagnostics
             )
             .unwrap(),
             Some(ImageGroup::from(SimpleImageGroup {
                 name: "2021-12-01 Fuji, Japan",
                 title: "Fuji, Japan",
-                date: Date::from_calendar_date(2021, Month::December, 01).unwrap(),
+                date: DateRange::Day(Date::from_calendar_date(2021, Month::December, 1).unwrap()),
                 images: &[("Summit", "some/path/2021-12-01 Fuji, Japan/Summit.webp")],
                 markdown_file: None,
             }))
         );
+        assert_eq!(
+            diagnostics.skipped,
+            vec![
+                Skipped {
+                    path: PathBuf::from("some/path/2021-12-01 Fuji, Japan/Valley"),
+                    reason: SkipReason::NestedDirectory,
+                },
+                Skipped {
+                    path: PathBuf::from("some/path/2021-12-01 Fuji, Japan/something.unknown"),
+                    reason: SkipReason::UnknownFileType,
+                },
+            ]
+        );
     }
     #[test]
     fn test_missing_date_in_dirname() {
         assert_eq!(
             ImageGroup::from_entries(
-                Path::new("2021-01 Fuji, Japan"),
-                &dir("some/path/2021-01 Fuji, Japan", &[("Summit.webp", false)])
+                Path::new("Fuji, Japan"),
+                &dir("some/path/Fuji, Japan", &[("Summit.webp", false)]),
+                &[],
+                &mut Diagnostics::default()
             )
             .unwrap(),
             None
         );
     }
+    #[test]
+    fn test_pages() {
+        let mut diagnostics = Diagnostics::default();
+        let pages = pages_from_entries(
+            &dir(
+                "some/path/pages",
+                &[
+                    ("Contact.md", false),
+                    ("About.md", false),
+                    ("About.de.md", false),
+                    ("about.md", false),
+                    ("Imprint.de.md", false),
+                    ("Notes.old.md", false),
+                    ("Photo.webp", false),
+                ],
+            ),
+            &["en".to_owned(), "de".to_owned()],
+            &mut diagnostics,
+        )
+        .unwrap();
+        // Only the configured languages count as translations.
+        assert_eq!(
+            pages.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(),
+            vec!["About", "Contact", "Notes.old"]
+        );
+        assert_eq!(
+            pages[0].markdown_file,
+            PathBuf::from("some/path/pages/About.md")
+        );
+        assert_eq!(
+            pages[0].translations,
+            BTreeMap::from([(
+                "de".to_owned(),
+                PathBuf::from("some/path/pages/About.de.md")
+            )])
+        );
+        assert!(pages[1].translations.is_empty());
+        assert_eq!(
+            diagnostics.skipped,
+            vec![
+                Skipped {
+                    path: PathBuf::from("some/path/pages/Photo.webp"),
+                    reason: SkipReason::NotAMarkdownFile,
+                },
+                Skipped {
+                    path: PathBuf::from("some/path/pages/about.md"),
+                    reason: SkipReason::DuplicatePageUrl,
+                },
+                Skipped {
+                    path: PathBuf::from("some/path/pages/Imprint.de.md"),
+                    reason: SkipReason::TranslationWithoutOriginal,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let mut diagnostics = Diagnostics::default();
+        assert!(diagnostics.check(true).is_ok());
+        diagnostics.skipped.push(Skipped {
+            path: PathBuf::from("some/path/Fuji, Japan"),
+            reason: SkipReason::MissingDate,
+        });
+        assert!(diagnostics.check(false).is_ok());
+        let err = diagnostics.check(true).unwrap_err().to_string();
+        assert!(err.contains("Skipped \"some/path/Fuji, Japan\""), "{}", err);
+    }
+    #[test]
+    fn test_year_in_dirname() {
+        assert_eq!(
+            ImageGroup::from_entries(
+                Path::new("2021 Fuji, Japan"),
+                &[],
+                &[],
+                &mut Diagnostics::default()
+            )
+            .unwrap(),
+            Some(ImageGroup::from(SimpleImageGroup {
+                name: "2021 Fuji, Japan",
+                title: "Fuji, Japan",
+                date: DateRange::Year(2021),
+                images: &[],
+                markdown_file: None,
+            }))
+        );
+    }
+    #[test]
+    fn test_month_in_dirname() {
+        assert_eq!(
+            ImageGroup::from_entries(
+                Path::new("2021-01 Fuji, Japan"),
+                &[],
+                &[],
+                &mut Diagnostics::default()
+            )
+            .unwrap(),
+            Some(ImageGroup::from(SimpleImageGroup {
+                name: "2021-01 Fuji, Japan",
+                title: "Fuji, Japan",
+                date: DateRange::Month(2021, Month::January),
+                images: &[],
+                markdown_file: None,
+            }))
+        );
+    }
+    #[test]
+    fn test_date_range_in_dirname() {
+        assert_eq!(
+            ImageGroup::from_entries(
+                Path::new("2021-01-01..2021-01-07 Fuji, Japan"),
+                &[],
+                &[],
+                &mut Diagnostics::default()
+            )
+            .unwrap(),
+            Some(ImageGroup::from(SimpleImageGroup {
+                name: "2021-01-01..2021-01-07 Fuji, Japan",
+                title: "Fuji, Japan",
+                date: DateRange::Days(
+                    Date::from_calendar_date(2021, Month::January, 1).unwrap(),
+                    Date::from_calendar_date(2021, Month::January, 7).unwrap()
+                ),
+                images: &[],
+                markdown_file: None,
+            }))
+        );
+    }
+    #[test]
+    fn test_invalid_dates_in_dirname() {
+        for name in [
+            "2021-13 Fuji, Japan",
+            "2021-02-30 Fuji, Japan",
+            "2021-01-07..2021-01-01 Fuji, Japan",
+        ] {
+            assert!(
+                ImageGroup::from_entries(Path::new(name), &[], &[], &mut Diagnostics::default())
+                    .is_err(),
+                "{}",
+                name
+            );
+        }
+    }
 }
@@ -0,0 +1,273 @@
+//! Reads metadata from markdown front matter and XMP sidecar files.
+//!
+//! A markdown file can start with a front matter block of `key: value` lines:
+//!
+//! ```markdown
+//! ---
+//! tags: hiking, family
+//! cover: Summit
+//! unreferenced_images: append
+//! title.de: Wanderung
+//! ---
+//! Some text.
+//! ```
+use crate::config::UnreferencedImages;
+use crate::error::{PathErrorContext, path_error};
+use crate::model::tag_key;
+
+use anyhow::Result;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FRONT_MATTER_DELIMITER: &str = "---";
+
+/// The metadata of an image group from the front matter of its markdown file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    /// Tags of the image group.
+    pub tags: Vec<String>,
+    /// The name of the cover image of the image group.
+    pub cover: Option<String>,
+    /// What to do with images missing from the markdown file.
+    pub unreferenced_images: Option<UnreferencedImages>,
+    /// Titles of the image group in other languages, from `title.<language>` keys.
+    pub titles: BTreeMap<String, String>,
+}
+
+/// Splits the contents of a markdown file into the front matter and the markdown content.
+///
+/// The front matter is [`None`] if the file doesn't start with a front matter block.
+pub fn split_front_matter(input: &str) -> (Option<&str>, &str) {
+    let Some(rest) = input
+        .strip_prefix(FRONT_MATTER_DELIMITER)
+        .and_then(|rest| rest.strip_prefix('\n'))
+    else {
+        return (None, input);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == FRONT_MATTER_DELIMITER {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    // Without a closing delimiter, this is just a thematic break.
+    (None, input)
+}
+
+/// Reads the front matter of a markdown file.
+pub fn read_front_matter(markdown_file: &Path) -> Result<FrontMatter> {
+    let input = fs::read_to_string(markdown_file)
+        .path_context("Failed to open image group markdown file", markdown_file)?;
+    match split_front_matter(&input).0 {
+        Some(front_matter) => parse_front_matter(front_matter, markdown_file),
+        None => Ok(FrontMatter::default()),
+    }
+}
+
+fn parse_front_matter(front_matter: &str, path: &Path) -> Result<FrontMatter> {
+    let mut res = FrontMatter::default();
+    for line in front_matter.lines().filter(|l| !l.trim().is_empty()) {
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(path_error(
+                &format!("Invalid front matter line \"{}\"", line),
+                path,
+            ));
+        };
+        match key.trim() {
+            "tags" => res.tags = parse_list(value),
+            "cover" => res.cover = Some(value.trim().to_owned()),
+            "unreferenced_images" => {
+                res.unreferenced_images = Some(match value.trim() {
+                    "error" => UnreferencedImages::Error,
+                    "warn" => UnreferencedImages::Warn,
+                    "append" => UnreferencedImages::Append,
+                    value => {
+                        return Err(path_error(
+                            &format!(
+                                "Invalid value \"{}\" for unreferenced_images, expected error, warn, or append",
+                                value
+                            ),
+                            path,
+                        ));
+                    }
+                })
+            }
+            key if key.starts_with("title.") && key.len() > "title.".len() => {
+                res.titles
+                    .insert(key["title.".len()..].to_owned(), value.trim().to_owned());
+            }
+            key => {
+                return Err(path_error(
+                    &format!("Unknown front matter key \"{}\"", key),
+                    path,
+                ));
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Parses a comma-separated list, optionally in brackets like `[a, b]`.
+fn parse_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    normalize_tags(value.split(','))
+}
+
+/// Trims tags and removes empty and duplicate tags, keeping the first occurrence.
+///
+/// Tags without letters or digits like `★` are removed as well because they have no
+/// [`tag_key`] and thus no page of their own.
+fn normalize_tags<'a>(tags: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut res = Vec::<String>::new();
+    for tag in tags.map(str::trim).filter(|t| !tag_key(t).is_empty()) {
+        if !res.iter().any(|t| t == tag) {
+            res.push(tag.to_owned());
+        }
+    }
+    res
+}
+
+/// Whether the file is an XMP sidecar file.
+pub fn is_sidecar(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "xmp")
+}
+
+/// The possible paths of the XMP sidecar file of an image, in order of preference.
+///
+/// Both `Image.webp.xmp` and `Image.xmp` are common conventions.
+pub fn sidecar_paths(image_path: &Path) -> [PathBuf; 2] {
+    let mut full = image_path.as_os_str().to_owned();
+    full.push(".xmp");
+    [PathBuf::from(full), image_path.with_extension("xmp")]
+}
+
+/// Reads the keywords (`dc:subject`) of an XMP sidecar file.
+pub fn read_xmp_tags(sidecar: &Path) -> Result<Vec<String>> {
+    let input = fs::read_to_string(sidecar).path_context("Failed to open XMP file", sidecar)?;
+    Ok(parse_xmp_tags(&input))
+}
+
+fn parse_xmp_tags(input: &str) -> Vec<String> {
+    let subject = Regex::new(r"(?s)<dc:subject>(.*?)</dc:subject>").unwrap();
+    let item = Regex::new(r"(?s)<rdf:li(?:\s[^>]*)?>(.*?)</rdf:li>").unwrap();
+    let Some(c) = subject.captures(input) else {
+        return Vec::new();
+    };
+    let tags = item
+        .captures_iter(&c[1])
+        .map(|c| unescape_xml(&c[1]))
+        .collect::<Vec<_>>();
+    normalize_tags(tags.iter().map(String::as_str))
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        FrontMatter, UnreferencedImages, parse_front_matter, parse_xmp_tags, sidecar_paths,
+        split_front_matter,
+    };
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_split_front_matter() {
+        assert_eq!(
+            split_front_matter("---\ntags: a\n---\nText\n"),
+            (Some("tags: a\n"), "Text\n")
+        );
+        assert_eq!(split_front_matter("Text\n"), (None, "Text\n"));
+        // A thematic break without a closing delimiter is not front matter.
+        assert_eq!(split_front_matter("---\nText\n"), (None, "---\nText\n"));
+    }
+
+    #[test]
+    fn test_parse_front_matter() {
+        let path = Path::new("index.md");
+        assert_eq!(
+            parse_front_matter("tags: hiking, family,  hiking\n", path).unwrap(),
+            FrontMatter {
+                tags: vec!["hiking".to_owned(), "family".to_owned()],
+                cover: None,
+                unreferenced_images: None,
+                titles: BTreeMap::new(),
+            }
+        );
+        assert_eq!(
+            parse_front_matter("cover:  Summit \n", path).unwrap().cover,
+            Some("Summit".to_owned())
+        );
+        assert_eq!(
+            parse_front_matter("tags: [birds]\n\n", path).unwrap().tags,
+            vec!["birds".to_owned()]
+        );
+        assert_eq!(
+            parse_front_matter("tags: ★, !!!, Zürich\n", path)
+                .unwrap()
+                .tags,
+            vec!["Zürich".to_owned()]
+        );
+        assert_eq!(
+            parse_front_matter("unreferenced_images: warn\n", path)
+                .unwrap()
+                .unreferenced_images,
+            Some(UnreferencedImages::Warn)
+        );
+        assert_eq!(
+            parse_front_matter("title.de: Wanderung\n", path)
+                .unwrap()
+                .titles,
+            BTreeMap::from([("de".to_owned(), "Wanderung".to_owned())])
+        );
+        assert!(parse_front_matter("unreferenced_images: ignore\n", path).is_err());
+        assert!(parse_front_matter("title.: x\n", path).is_err());
+        assert!(parse_front_matter("unknown: x\n", path).is_err());
+        assert!(parse_front_matter("no colon\n", path).is_err());
+    }
+
+    #[test]
+    fn test_parse_xmp_tags() {
+        let xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description xmlns:dc="http://purl.org/dc/elements/1.1/">
+   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>birds</rdf:li>
+     <rdf:li xml:lang="en">Birds &amp; Bees</rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+        assert_eq!(
+            parse_xmp_tags(xmp),
+            vec!["birds".to_owned(), "Birds & Bees".to_owned()]
+        );
+        assert!(parse_xmp_tags("<x:xmpmeta/>").is_empty());
+    }
+
+    #[test]
+    fn test_sidecar_paths() {
+        assert_eq!(
+            sidecar_paths(Path::new("dir/Summit.webp")),
+            [
+                PathBuf::from("dir/Summit.webp.xmp"),
+                PathBuf::from("dir/Summit.xmp")
+            ]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! A static site generator for photo galleries.
+//!
+//! The build pipeline has two stages: [`read_gallery`] reads the source directory into a
+//! [`Gallery`], and [`write_gallery`] writes the gallery to the output directory.
+//! [`build`] runs both stages.
+//!
+//! ```no_run
+//! use gallery::{Config, GalleryOrder};
+//!
+//! let config = Config::builder("build")
+//!     .page_title("My photos")
+//!     .order(GalleryOrder::OldestFirst)
+//!     .build();
+//! let (gallery, diagnostics) = gallery::read_gallery("photos".as_ref(), false, &config.languages)?;
+//! for skipped in &diagnostics.skipped {
+//!     eprintln!("Warning: {}", skipped);
+//! }
+//! gallery::write_gallery(&gallery, &config)?;
+//! # Ok::<(), gallery::Error>(())
+//! ```
+mod config;
+mod error;
+mod input;
+mod model;
+mod output;
+
+pub use config::{
+    Config, ConfigBuilder, DateFormat, ErrorMode, GalleryOrder, ImageLayout, OutputFormat,
+    OverviewLayout, RunMode, UnreferencedImages,
+};
+pub use error::{Error, Stage};
+pub use input::{Diagnostics, SkipReason, Skipped, Unreferenced};
+pub use model::{DateRange, Gallery, Image, ImageGroup, Page};
+pub use output::Category;
+
+use anyhow::Context;
+use std::path::Path;
+
+/// Reads the source directory into a [`Gallery`].
+///
+/// The returned [`Diagnostics`] list the skipped input entries.
+/// In strict mode, skipped input entries are an error instead.
+///
+/// Markdown files like `index.de.md` are translations if their language is one of `languages`
+/// other than the first, the default language. These are usually [`Config::languages`].
+pub fn read_gallery(
+    input_path: &Path,
+    strict: bool,
+    languages: &[String],
+) -> Result<(Gallery, Diagnostics), Error> {
+    let mut diagnostics = Diagnostics::default();
+    let gallery = input::gallery_from_dir(input_path, languages, &mut diagnostics)
+        .with_context(|| "Failed to read gallery")
+        .map_err(|e| Error::from_anyhow(e, Stage::Input))?;
+    diagnostics.check(strict)?;
+    Ok((gallery, diagnostics))
+}
+
+/// Writes the gallery to the output directory.
+///
+/// The returned [`Diagnostics`] list the images left out of image group pages, see
+/// [`UnreferencedImages::Warn`].
+pub fn write_gallery(gallery: &Gallery, config: &Config) -> Result<Diagnostics, Error> {
+    let unreferenced = output::write_files(gallery, config)
+        .with_context(|| "Failed to write gallery")
+        .map_err(|e| Error::from_anyhow(e, Stage::Output))?;
+    Ok(Diagnostics {
+        unreferenced,
+        ..Diagnostics::default()
+    })
+}
+
+/// Reads the source directory and writes the gallery to the output directory.
+///
+/// Returns the [`Diagnostics`] of both stages, see [`read_gallery`] and [`write_gallery`].
+pub fn build(input_path: &Path, strict: bool, config: &Config) -> Result<Diagnostics, Error> {
+    let (gallery, mut diagnostics) = read_gallery(input_path, strict, &config.languages)?;
+    diagnostics.unreferenced = write_gallery(&gallery, config)?.unreferenced;
+    Ok(diagnostics)
+}
@@ -1,16 +1,20 @@
 //! A static site generator for photo galleries.
-mod config;
-mod error;
-mod input;
-mod model;
-mod output;
-
-use anyhow::{Context, Result};
-use clap::Parser;
-use config::{Config, GalleryOrder, RunMode};
-use std::path::PathBuf;
+//!
+//! This is the commandline interface to the library.
+use clap::{Parser, ValueEnum};
+use gallery::{
+    Config, DateFormat, ErrorMode, GalleryOrder, ImageLayout, OutputFormat, OverviewLayout,
+    RunMode, Stage, UnreferencedImages,
+};
+use serde::Serialize;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 /// Commandline arguments.
+///
+/// Exit status: 0 on success, 2 for usage errors, 3 if the input could not be read,
+/// and 4 if the gallery could not be written.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -18,6 +22,10 @@ struct Cli {
     #[arg(long = "dry_run")]
     dry_run: bool,
 
+    /// If set, continue past errors and report all of them at the end.
+    #[arg(long = "keep_going")]
+    keep_going: bool,
+
     /// If set, output groups in chronological order.
     #[arg(long = "oldest_first")]
     oldest_first: bool,
@@ -37,6 +45,126 @@ struct Cli {
     /// An HTML snippet for the page footer.
     #[arg(long)]
     footer: Option<String>,
+
+    /// The number of image groups per overview and archive page. By default, all image groups are
+    /// on a single page.
+    #[arg(long = "page_size")]
+    page_size: Option<NonZeroUsize>,
+
+    /// If set, write an archive page per month in addition to the archive page per year.
+    #[arg(long = "month_archives")]
+    month_archives: bool,
+
+    /// If set, render markdown as plain CommonMark without tables, footnotes, and other extensions.
+    #[arg(long = "plain_markdown")]
+    plain_markdown: bool,
+
+    /// If set, fail on skipped input entries instead of printing warnings.
+    #[arg(long)]
+    strict: bool,
+
+    /// The kind of output to write.
+    #[arg(long, value_enum, default_value_t = Format::Html)]
+    format: Format,
+
+    /// How the overview shows image groups.
+    #[arg(long, value_enum, default_value_t = Overview::Images)]
+    overview: Overview,
+
+    /// How to arrange the images on the overview and on image group pages without index.md.
+    #[arg(long, value_enum, default_value_t = Layout::Grid)]
+    layout: Layout,
+
+    /// What to do with images that index.md doesn't reference.
+    /// The front matter of index.md can override this per image group.
+    #[arg(long = "unreferenced_images", value_enum, default_value_t = Unreferenced::Error)]
+    unreferenced_images: Unreferenced,
+
+    /// The comma-separated language codes of the gallery, for example "en,de". The first language
+    /// is the default, the others get their own page tree in a subdirectory like "de/".
+    /// Translations are read from files like index.de.md.
+    #[arg(long, value_delimiter = ',', value_parser = parse_language)]
+    languages: Vec<String>,
+
+    /// A directory with handlebars templates that replace the built-in templates, for example
+    /// overview.handlebars, and partials in its "partials" subdirectory, for example
+    /// partials/footer.handlebars.
+    #[arg(long)]
+    theme: Option<PathBuf>,
+
+    /// How to show the dates of image groups. The patterns and month names of each format come
+    /// from the translation files of the theme, <theme>/translations/<lang>.json.
+    #[arg(long = "date_format", value_enum, default_value_t = Dates::Numeric)]
+    date_format: Dates,
+
+    /// The format of warnings and errors on stderr.
+    #[arg(
+        long = "message_format",
+        alias = "message-format",
+        value_enum,
+        default_value_t = MessageFormat::Human
+    )]
+    message_format: MessageFormat,
+}
+
+/// The kind of output to write.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// HTML pages.
+    Html,
+    /// The gallery model as gallery.json, for example for a separate frontend.
+    Json,
+}
+
+/// How the overview shows image groups.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Overview {
+    /// One card per image.
+    Images,
+    /// One card per image group with its cover image, linking to the image group page.
+    Covers,
+}
+
+/// How to arrange the images.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Layout {
+    /// A grid of cropped images.
+    Grid,
+    /// Rows of equal height with uncropped images.
+    Justified,
+    /// Columns of equal width with uncropped images.
+    Masonry,
+}
+
+/// What to do with images that index.md doesn't reference.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Unreferenced {
+    /// Fail to render the image group.
+    Error,
+    /// Leave the images out of the image group page and print a warning.
+    Warn,
+    /// Show the images in a grid at the end of the image group page.
+    Append,
+}
+
+/// How to show dates.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Dates {
+    /// Numeric dates in the order of the language, ISO 8601 for English.
+    Numeric,
+    /// ISO 8601 dates in all languages.
+    Iso,
+    /// Dates with the month name in the language of the page.
+    Long,
+}
+
+/// The format of warnings and errors.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum MessageFormat {
+    /// Human-readable messages.
+    Human,
+    /// One JSON object per line.
+    Json,
 }
 
 impl Cli {
@@ -48,6 +176,52 @@ impl Cli {
         }
     }
 
+    fn error_mode(&self) -> ErrorMode {
+        if self.keep_going {
+            ErrorMode::KeepGoing
+        } else {
+            ErrorMode::FailFast
+        }
+    }
+
+    fn format(&self) -> OutputFormat {
+        match self.format {
+            Format::Html => OutputFormat::Html,
+            Format::Json => OutputFormat::Json,
+        }
+    }
+
+    fn overview_layout(&self) -> OverviewLayout {
+        match self.overview {
+            Overview::Images => OverviewLayout::Images,
+            Overview::Covers => OverviewLayout::Covers,
+        }
+    }
+
+    fn image_layout(&self) -> ImageLayout {
+        match self.layout {
+            Layout::Grid => ImageLayout::Grid,
+            Layout::Justified => ImageLayout::Justified,
+            Layout::Masonry => ImageLayout::Masonry,
+        }
+    }
+
+    fn unreferenced_images(&self) -> UnreferencedImages {
+        match self.unreferenced_images {
+            Unreferenced::Error => UnreferencedImages::Error,
+            Unreferenced::Warn => UnreferencedImages::Warn,
+            Unreferenced::Append => UnreferencedImages::Append,
+        }
+    }
+
+    fn date_format(&self) -> DateFormat {
+        match self.date_format {
+            Dates::Numeric => DateFormat::Numeric,
+            Dates::Iso => DateFormat::Iso,
+            Dates::Long => DateFormat::Long,
+        }
+    }
+
     fn order(&self) -> GalleryOrder {
         if self.oldest_first {
             GalleryOrder::OldestFirst
@@ -57,28 +231,166 @@ impl Cli {
     }
 
     fn output_config(&self) -> Config {
-        Config {
-            output_path: PathBuf::from(&self.output),
-            order: self.order(),
-            run_mode: self.run_mode(),
-            page_title: self.page_title.to_owned(),
-            page_footer: self.footer.to_owned(),
+        Config::builder(&self.output)
+            .order(self.order())
+            .run_mode(self.run_mode())
+            .error_mode(self.error_mode())
+            .format(self.format())
+            .overview_layout(self.overview_layout())
+            .image_layout(self.image_layout())
+            .page_title(&self.page_title)
+            .page_footer(self.footer.to_owned())
+            .page_size(self.page_size)
+            .month_archives(self.month_archives)
+            .markdown_extensions(!self.plain_markdown)
+            .unreferenced_images(self.unreferenced_images())
+            .languages(self.languages.clone())
+            .date_format(self.date_format())
+            .theme(self.theme.clone())
+            .build()
+    }
+}
+
+/// Accepts language codes like "de" or "pt-BR", which also name the output subdirectories.
+fn parse_language(value: &str) -> Result<String, String> {
+    let (language, region) = match value.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (value, None),
+    };
+    if (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && region.is_none_or(|r| !r.is_empty() && r.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        Ok(value.to_owned())
+    } else {
+        Err(format!(
+            "\"{}\" is not a language code like \"de\" or \"pt-BR\"",
+            value
+        ))
+    }
+}
+
+/// Returns the exit status for a failed run.
+fn exit_code(e: &gallery::Error) -> ExitCode {
+    match e.stage() {
+        Stage::Input => ExitCode::from(3),
+        Stage::Output => ExitCode::from(4),
+    }
+}
+
+/// A warning or error in JSON form, see [`MessageFormat::Json`].
+#[derive(Serialize)]
+struct JsonMessage<'a> {
+    level: &'a str,
+    message: String,
+    /// The offending file or directory, if known.
+    path: Option<&'a Path>,
+    /// The chain of underlying errors, outermost first.
+    causes: Vec<String>,
+    /// The kind of work that failed, for errors collected with `--keep_going`.
+    category: Option<String>,
+}
+
+impl MessageFormat {
+    fn warning(&self, message: &str, path: &Path) {
+        match self {
+            MessageFormat::Human => eprintln!("Warning: {}", message),
+            MessageFormat::Json => print_json(&JsonMessage {
+                level: "warning",
+                message: message.to_owned(),
+                path: Some(path),
+                causes: Vec::new(),
+                category: None,
+            }),
         }
     }
+
+    fn error(&self, e: &gallery::Error) {
+        match self {
+            MessageFormat::Human => {
+                eprintln!("Error: {}", e);
+                let causes = causes(e);
+                if !causes.is_empty() {
+                    eprintln!("\nCaused by:");
+                    for cause in causes {
+                        eprintln!("    {}", cause);
+                    }
+                }
+            }
+            MessageFormat::Json => match e {
+                // Report collected errors individually so that each one keeps its path.
+                gallery::Error::Collected(errors) => {
+                    for (category, e) in errors {
+                        print_json(&json_error(e, Some(category.to_string())));
+                    }
+                }
+                _ => print_json(&json_error(e, None)),
+            },
+        }
+    }
+}
+
+/// Returns the chain of underlying errors, outermost first.
+fn causes(e: &gallery::Error) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut source = std::error::Error::source(e);
+    while let Some(s) = source {
+        res.push(s.to_string());
+        source = s.source();
+    }
+    res
+}
+
+fn json_error(e: &gallery::Error, category: Option<String>) -> JsonMessage<'_> {
+    JsonMessage {
+        level: "error",
+        message: e.to_string(),
+        path: e.path(),
+        causes: causes(e),
+        category,
+    }
 }
 
-/// Generates a photo gallery based on the provided commandline arguments.
+fn print_json(message: &JsonMessage) {
+    eprintln!(
+        "{}",
+        serde_json::to_string(message).expect("messages are always serializable")
+    );
+}
+
+/// Generates a photo gallery based on the parsed commandline arguments.
+fn run(args: &Cli) -> Result<(), gallery::Error> {
+    let config = args.output_config();
+    let (gallery, diagnostics) =
+        gallery::read_gallery(Path::new(&args.input), args.strict, &config.languages)?;
+    for skipped in &diagnostics.skipped {
+        args.message_format
+            .warning(&skipped.to_string(), &skipped.path);
+    }
+    let diagnostics = gallery::write_gallery(&gallery, &config)?;
+    for unreferenced in &diagnostics.unreferenced {
+        args.message_format
+            .warning(&unreferenced.to_string(), &unreferenced.path);
+    }
+    Ok(())
+}
+
+/// Generates a photo gallery based on the provided commandline arguments
+/// and returns the exit status.
 ///
 /// To use the arguments provided by the system, pass in [`std::env::args_os()`].
-fn run_on_args(args: impl Iterator<Item = std::ffi::OsString>) -> Result<()> {
+fn run_on_args(args: impl Iterator<Item = std::ffi::OsString>) -> ExitCode {
+    // Usage errors exit with status 2.
     let args = Cli::parse_from(args);
-    let input_path = PathBuf::from(&args.input);
-    let gallery = input::gallery_from_dir(&input_path).with_context(|| "Failed to read gallery")?;
-    output::write_files(&gallery, &args.output_config()).with_context(|| "Failed to write gallery")
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            args.message_format.error(&e);
+            exit_code(&e)
+        }
+    }
 }
 
-fn main() {
-    if let Err(e) = run_on_args(std::env::args_os()) {
-        println!("Error: {:?}", e);
-    }
+fn main() -> ExitCode {
+    run_on_args(std::env::args_os())
 }
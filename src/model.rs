@@ -1,13 +1,18 @@
 //! Data structures to represent image galleries throughput the program.
+use crate::config::UnreferencedImages;
 use crate::error::PathErrorContext;
 
 use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::path::{Path, PathBuf};
-use time::Date;
+use time::{Date, Month};
 
 /// An input image.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Images are ordered by name in natural order, so `Image 2` comes before `Image 10`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Image {
     /// The user-visible name of the image.
     pub name: String,
@@ -15,39 +20,88 @@ pub struct Image {
     pub path: PathBuf,
     /// The file name of the source image.
     pub file_name: PathBuf,
+    /// Tags of the image from its XMP sidecar file.
+    pub tags: Vec<String>,
 }
 
 /// A list of input images.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ImageGroup {
     /// The path to the image group directory relative to the base directory.
     pub path: PathBuf,
     /// The user-visible title of the image group.
     pub title: String,
     /// The date of the image group.
-    pub date: Date,
+    pub date: DateRange,
     /// The contained images.
-    /// Sorted alphabetically.
+    /// Sorted by name in natural order.
     pub images: Vec<Image>,
     /// An optional markdown file to explain the image group.
     /// Not yet fully implemented.
     pub markdown_file: Option<PathBuf>,
+    /// Tags of the image group from the front matter of the markdown file.
+    pub tags: Vec<String>,
+    /// The name of the cover image from the front matter of the markdown file.
+    pub cover: Option<String>,
+    /// What to do with images missing from the markdown file, from its front matter.
+    /// Overrides [`crate::Config::unreferenced_images`].
+    pub unreferenced_images: Option<UnreferencedImages>,
+    /// Titles in other languages from the front matter, keyed by language code.
+    pub titles: BTreeMap<String, String>,
+    /// Markdown files in other languages like `index.de.md`, keyed by language code.
+    pub translations: BTreeMap<String, PathBuf>,
 }
 
 /// A gallery of images.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Gallery {
     /// The list of image groups in the gallery.
     /// Sorted by date (most recent first).
     pub image_groups: Vec<ImageGroup>,
+    /// Standalone pages such as an about page.
+    /// Sorted by title in natural order.
+    pub pages: Vec<Page>,
+    /// An optional markdown file with an introduction for the overview page.
+    pub index_file: Option<PathBuf>,
+    /// The introduction in other languages like `index.de.md`, keyed by language code.
+    pub index_translations: BTreeMap<String, PathBuf>,
+}
+
+/// A standalone page from a markdown file in the `pages` directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    /// The user-visible title of the page.
+    pub title: String,
+    /// The full path to the markdown file.
+    pub markdown_file: PathBuf,
+    /// Markdown files in other languages like `About.de.md`, keyed by language code.
+    pub translations: BTreeMap<String, PathBuf>,
+}
+
+/// The date of an image group with the precision given in the group directory name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateRange {
+    /// A whole year, for example "2021".
+    Year(i32),
+    /// A whole month, for example "2021-01".
+    Month(i32, Month),
+    /// A single day, for example "2021-01-01".
+    Day(Date),
+    /// An inclusive range of days, for example "2021-01-01..2021-01-07".
+    Days(Date, Date),
 }
 
 /// Different thumbnail types for different use cases.
 ///
 /// The overview page uses small thumbnails, the image group pages use large thumbnails.
+/// Cropped thumbnails all have the same aspect ratio, uncropped thumbnails keep the aspect ratio
+/// of the source image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThumbnailType {
     Small,
     Large,
+    SmallUncropped,
+    LargeUncropped,
 }
 
 impl Image {
@@ -61,10 +115,25 @@ impl Image {
                 .to_owned(),
             path,
             file_name,
+            tags: Vec::new(),
         })
     }
 }
 
+impl Ord for Image {
+    fn cmp(&self, other: &Self) -> Ordering {
+        natural_cmp(&self.name, &other.name)
+            .then_with(|| self.path.cmp(&other.path))
+            .then_with(|| self.file_name.cmp(&other.file_name))
+    }
+}
+
+impl PartialOrd for Image {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl ImageGroup {
     /// The URL to this image group, relative to the base directory.
     /// The return value is guaranteed to consist only of ASCII characters.
@@ -87,6 +156,8 @@ impl ImageGroup {
         let size = match thumbnail_type {
             ThumbnailType::Small => "small",
             ThumbnailType::Large => "large",
+            ThumbnailType::SmallUncropped => "small-uncropped",
+            ThumbnailType::LargeUncropped => "large-uncropped",
         };
         Ok(PathBuf::from("thumbnails").join(size).join(&suffix))
     }
@@ -95,6 +166,133 @@ impl ImageGroup {
     pub fn image_filename(&self, img: &Image) -> Result<PathBuf> {
         to_web_path(&self.path.join(&img.file_name))
     }
+    /// Whether an image in this image group has the tag, either directly or through the image group.
+    pub fn image_has_tag(&self, img: &Image, tag: &str) -> bool {
+        let key = tag_key(tag);
+        self.tags.iter().chain(&img.tags).any(|t| tag_key(t) == key)
+    }
+}
+
+/// The URL to the page of a tag, relative to the base directory.
+/// The return value is guaranteed to consist only of ASCII characters.
+pub fn tag_url(tag: &str) -> PathBuf {
+    PathBuf::from("tags").join(tag_key(tag) + ".html")
+}
+
+/// Identifies a tag. Tags that only differ in case, accents, or punctuation are the same tag.
+pub fn tag_key(tag: &str) -> String {
+    slug::slugify(tag)
+}
+
+impl Gallery {
+    /// The gallery in another language.
+    ///
+    /// Titles and markdown files are replaced by their translations where available.
+    /// URLs don't change, so every language needs its own output directory.
+    pub fn translated(&self, language: &str) -> Gallery {
+        let translate = |file: &Option<PathBuf>, translations: &BTreeMap<String, PathBuf>| {
+            translations.get(language).cloned().or_else(|| file.clone())
+        };
+        Gallery {
+            image_groups: self
+                .image_groups
+                .iter()
+                .map(|g| ImageGroup {
+                    title: g.titles.get(language).unwrap_or(&g.title).clone(),
+                    markdown_file: translate(&g.markdown_file, &g.translations),
+                    ..g.clone()
+                })
+                .collect(),
+            pages: self
+                .pages
+                .iter()
+                .map(|p| Page {
+                    markdown_file: p
+                        .translations
+                        .get(language)
+                        .unwrap_or(&p.markdown_file)
+                        .clone(),
+                    ..p.clone()
+                })
+                .collect(),
+            index_file: translate(&self.index_file, &self.index_translations),
+            index_translations: self.index_translations.clone(),
+        }
+    }
+}
+
+impl Page {
+    /// The URL to this page, relative to the base directory.
+    /// The return value is guaranteed to consist only of ASCII characters.
+    pub fn url(&self) -> PathBuf {
+        PathBuf::from("pages").join(slug::slugify(&self.title) + ".html")
+    }
+}
+
+impl ThumbnailType {
+    /// Whether the thumbnail is cropped to a fixed aspect ratio.
+    pub fn is_cropped(&self) -> bool {
+        matches!(self, ThumbnailType::Small | ThumbnailType::Large)
+    }
+}
+
+impl DateRange {
+    /// The first day covered by this date range.
+    pub fn first_day(&self) -> Date {
+        match *self {
+            DateRange::Year(year) => Date::from_calendar_date(year, Month::January, 1),
+            DateRange::Month(year, month) => Date::from_calendar_date(year, month, 1),
+            DateRange::Day(date) | DateRange::Days(date, _) => Ok(date),
+        }
+        .expect("the first day of a month is always valid")
+    }
+    /// The last day covered by this date range.
+    pub fn last_day(&self) -> Date {
+        match *self {
+            DateRange::Year(year) => Date::from_calendar_date(year, Month::December, 31),
+            DateRange::Month(year, month) => {
+                Date::from_calendar_date(year, month, month.length(year))
+            }
+            DateRange::Day(date) | DateRange::Days(_, date) => Ok(date),
+        }
+        .expect("the last day of a month is always valid")
+    }
+    /// A number to break ties between date ranges covering the same days.
+    fn precision(&self) -> u8 {
+        match self {
+            DateRange::Year(_) => 0,
+            DateRange::Month(..) => 1,
+            DateRange::Day(_) => 2,
+            DateRange::Days(..) => 3,
+        }
+    }
+}
+
+/// Date ranges are ordered by their first day, then by their last day.
+impl Ord for DateRange {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.first_day()
+            .cmp(&other.first_day())
+            .then_with(|| self.last_day().cmp(&other.last_day()))
+            .then_with(|| self.precision().cmp(&other.precision()))
+    }
+}
+
+impl PartialOrd for DateRange {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DateRange::Year(year) => write!(f, "{}", year),
+            DateRange::Month(year, month) => write!(f, "{}-{:02}", year, *month as u8),
+            DateRange::Day(date) => write!(f, "{}", date),
+            DateRange::Days(first, last) => write!(f, "{} – {}", first, last),
+        }
+    }
 }
 
 impl fmt::Display for ImageGroup {
@@ -121,10 +319,60 @@ impl fmt::Display for Gallery {
         for g in &self.image_groups {
             writeln!(f, "{}", g)?
         }
+        for p in &self.pages {
+            writeln!(
+                f,
+                "\"{}\" -> [{}]",
+                p.title,
+                p.markdown_file.to_string_lossy()
+            )?
+        }
         Ok(())
     }
 }
 
+/// Compares two strings in natural order.
+///
+/// Runs of digits compare by their numeric value, so "IMG_2" sorts before "IMG_10".
+/// Other text compares case-insensitively with accents folded, so "Zürich" sorts next to "Zurich".
+/// Strings that only differ in these respects fall back to plain string order.
+pub fn natural_cmp(lhs: &str, rhs: &str) -> Ordering {
+    natural_sort_key(lhs)
+        .cmp(&natural_sort_key(rhs))
+        .then_with(|| lhs.cmp(rhs))
+}
+
+/// A chunk of a string for the purpose of natural ordering.
+/// Numbers sort before text, matching the order of digits and letters in ASCII.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum NaturalChunk {
+    /// A run of digits without leading zeros, prefixed by its length to compare numerically.
+    Number(usize, String),
+    /// A run of non-digits, folded to lowercase ASCII.
+    Text(String),
+}
+
+fn natural_sort_key(s: &str) -> Vec<NaturalChunk> {
+    let folded = deunicode::deunicode(s).to_lowercase();
+    let mut chunks = Vec::new();
+    let mut rest = folded.as_str();
+    while let Some(c) = rest.chars().next() {
+        let is_digit = c.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != is_digit)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(if is_digit {
+            let digits = chunk.trim_start_matches('0');
+            NaturalChunk::Number(digits.len(), digits.to_owned())
+        } else {
+            NaturalChunk::Text(chunk.to_owned())
+        });
+        rest = tail;
+    }
+    chunks
+}
+
 /// Converts a path into something suitable for a URL. The resulting path consists of a single component.
 fn to_web_path(path: &Path) -> Result<PathBuf> {
     let p = path
@@ -139,7 +387,9 @@ fn to_web_path(path: &Path) -> Result<PathBuf> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Image, ImageGroup, ThumbnailType, to_web_path};
+    use super::{DateRange, Image, ImageGroup, ThumbnailType, natural_cmp, tag_url, to_web_path};
+    use std::cmp::Ordering;
+    use std::collections::BTreeMap;
     use std::path::{Path, PathBuf};
     use time::{Date, Month};
 
@@ -182,6 +432,98 @@ mod tests {
         );
     }
 
+    // Tests for natural_cmp.
+
+    #[test]
+    fn natural_cmp_numbers_compare_numerically() {
+        assert_eq!(natural_cmp("IMG_2", "IMG_10"), Ordering::Less);
+        assert_eq!(natural_cmp("IMG_10", "IMG_9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn natural_cmp_leading_zeros() {
+        assert_eq!(natural_cmp("IMG_002", "IMG_10"), Ordering::Less);
+        assert_eq!(natural_cmp("IMG_01", "IMG_1"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_cmp_ignores_case_and_accents() {
+        assert_eq!(natural_cmp("apple", "Banana"), Ordering::Less);
+        assert_eq!(natural_cmp("Zürich", "Zurich 2"), Ordering::Less);
+        assert_eq!(natural_cmp("Ölberg", "Pass"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_cmp_equal() {
+        assert_eq!(natural_cmp("Summit 3", "Summit 3"), Ordering::Equal);
+    }
+
+    #[test]
+    fn images_sort_naturally() {
+        let mut images = ["IMG_10", "IMG_2", "IMG_1"].map(|name| {
+            Image::new(PathBuf::from(name.to_owned() + ".webp"), PathBuf::new()).unwrap()
+        });
+        images.sort();
+        assert_eq!(
+            images.map(|i| i.name),
+            ["IMG_1".to_owned(), "IMG_2".to_owned(), "IMG_10".to_owned()]
+        );
+    }
+
+    // Tests for DateRange.
+
+    fn day(year: i32, month: Month, day: u8) -> Date {
+        Date::from_calendar_date(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn date_range_display() {
+        assert_eq!(DateRange::Year(2021).to_string(), "2021");
+        assert_eq!(
+            DateRange::Month(2021, Month::February).to_string(),
+            "2021-02"
+        );
+        assert_eq!(
+            DateRange::Day(day(2021, Month::February, 3)).to_string(),
+            "2021-02-03"
+        );
+        assert_eq!(
+            DateRange::Days(day(2021, Month::February, 3), day(2021, Month::February, 9))
+                .to_string(),
+            "2021-02-03 – 2021-02-09"
+        );
+    }
+
+    #[test]
+    fn date_range_first_and_last_day() {
+        let month = DateRange::Month(2024, Month::February);
+        assert_eq!(month.first_day(), day(2024, Month::February, 1));
+        assert_eq!(month.last_day(), day(2024, Month::February, 29));
+        let year = DateRange::Year(2021);
+        assert_eq!(year.first_day(), day(2021, Month::January, 1));
+        assert_eq!(year.last_day(), day(2021, Month::December, 31));
+    }
+
+    #[test]
+    fn date_range_order() {
+        let mut dates = vec![
+            DateRange::Day(day(2021, Month::February, 1)),
+            DateRange::Year(2021),
+            DateRange::Days(day(2021, Month::January, 1), day(2021, Month::January, 7)),
+            DateRange::Month(2021, Month::January),
+        ];
+        dates.sort();
+        assert_eq!(
+            dates,
+            vec![
+                DateRange::Days(day(2021, Month::January, 1), day(2021, Month::January, 7)),
+                DateRange::Month(2021, Month::January),
+                DateRange::Year(2021),
+                DateRange::Day(day(2021, Month::February, 1)),
+            ]
+        );
+    }
+
     // Tests for thumbnails.
 
     fn new_image_group(group_path: &str, image_path: &str) -> ImageGroup {
@@ -189,13 +531,19 @@ mod tests {
         ImageGroup {
             path: PathBuf::from(group_path),
             title: "ignored".to_owned(),
-            date: Date::from_calendar_date(2021, Month::January, 01).unwrap(),
+            date: DateRange::Day(Date::from_calendar_date(2021, Month::January, 1).unwrap()),
             images: vec![Image {
                 name: "ignored".to_owned(),
                 path: image_path.clone(),
                 file_name: PathBuf::from(image_path.file_name().unwrap()),
+                tags: vec!["Birds".to_owned()],
             }],
             markdown_file: None,
+            tags: vec!["Hiking".to_owned()],
+            cover: None,
+            unreferenced_images: None,
+            titles: BTreeMap::new(),
+            translations: BTreeMap::new(),
         }
     }
 
@@ -205,9 +553,9 @@ mod tests {
             "2021-01-01 Some group",
             "/some/path/2021-01-01 Some group/Some file.webp",
         );
-        let image = group.images.get(0).unwrap();
+        let image = group.images.first().unwrap();
         assert_eq!(
-            group.thumbnail_url(&image, &ThumbnailType::Small).unwrap(),
+            group.thumbnail_url(image, &ThumbnailType::Small).unwrap(),
             PathBuf::from("thumbnails/small/2021-01-01-some-group-some-file.webp")
         );
     }
@@ -218,9 +566,9 @@ mod tests {
             "2021-01-01 Some group",
             "/some/path/input/2021-01-01 Some group/Some file.jpeg",
         );
-        let image = group.images.get(0).unwrap();
+        let image = group.images.first().unwrap();
         assert_eq!(
-            group.thumbnail_url(&image, &ThumbnailType::Small).unwrap(),
+            group.thumbnail_url(image, &ThumbnailType::Small).unwrap(),
             // The thumbnail should be webp even for jpeg source files.
             PathBuf::from("thumbnails/small/2021-01-01-some-group-some-file.webp")
         );
@@ -232,10 +580,44 @@ mod tests {
             "2021-01-01 Some group",
             "/some/path/2021-01-01 Some group/Some file.webp",
         );
-        let image = group.images.get(0).unwrap();
+        let image = group.images.first().unwrap();
         assert_eq!(
-            group.thumbnail_url(&image, &ThumbnailType::Large).unwrap(),
+            group.thumbnail_url(image, &ThumbnailType::Large).unwrap(),
             PathBuf::from("thumbnails/large/2021-01-01-some-group-some-file.webp")
         );
     }
+
+    #[test]
+    fn thumbnail_path_uncropped() {
+        let group = new_image_group(
+            "2021-01-01 Some group",
+            "/some/path/2021-01-01 Some group/Some file.webp",
+        );
+        let image = group.images.first().unwrap();
+        assert_eq!(
+            group
+                .thumbnail_url(image, &ThumbnailType::SmallUncropped)
+                .unwrap(),
+            PathBuf::from("thumbnails/small-uncropped/2021-01-01-some-group-some-file.webp")
+        );
+    }
+
+    // Tests for tags.
+
+    #[test]
+    fn image_has_tag_directly_or_through_group() {
+        let group = new_image_group(
+            "2021-01-01 Some group",
+            "/some/path/2021-01-01 Some group/Some file.webp",
+        );
+        let image = group.images.first().unwrap();
+        assert!(group.image_has_tag(image, "birds"));
+        assert!(group.image_has_tag(image, "Hiking"));
+        assert!(!group.image_has_tag(image, "Family"));
+    }
+
+    #[test]
+    fn tag_url_is_slugified() {
+        assert_eq!(tag_url("Zürich & Co"), PathBuf::from("tags/zurich-co.html"));
+    }
 }
@@ -3,46 +3,231 @@
 //! Together with its submodules, this module writes everything including images, thumbnails, and HTML files.
 mod html;
 mod images;
+mod json;
 
-use crate::config::{Config, GalleryOrder, RunMode};
-use crate::error::PathErrorContext;
-use crate::model::Gallery;
+use crate::config::{
+    Config, ErrorMode, GalleryOrder, ImageLayout, OutputFormat, OverviewLayout, RunMode,
+    UnreferencedImages,
+};
+use crate::error::{Error, PathErrorContext, Stage};
+use crate::input::Unreferenced;
+use crate::model::{Gallery, ImageGroup, ThumbnailType};
 
 use anyhow::Result;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use std::fs;
 use std::path::Path;
+use std::sync::Arc;
+use std::{fmt, fs};
 
 /// A work item containing something to be written to disk.
 trait Item {
     /// Writes the item to disk.
     fn write(&self, config: &Config) -> Result<()>;
+    /// The kind of item for grouping errors.
+    fn category(&self) -> Category;
+}
+
+/// The kind of work that failed, used to group errors in the final report, see
+/// [`crate::Error::Collected`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Category {
+    /// HTML pages and `gallery.json`.
+    Pages,
+    /// The copies of the images and their placeholders and dimensions.
+    Images,
+    /// The thumbnails of the images.
+    Thumbnails,
+    /// Static assets such as CSS and Javascript files.
+    Assets,
 }
 
 /// Writes the gallery to disk.
-pub fn write_files(gallery: &Gallery, config: &Config) -> Result<()> {
-    let templates = html::make_templates()?;
-
-    // Create work items.
-    let items = {
-        let mut items = vec![];
-        for i in &gallery.image_groups {
-            items.extend(html::render_image_group_html(i, config, &templates)?);
-            items.extend(images::render_images(i, config)?);
+///
+/// Returns the images left out of image group pages, see [`UnreferencedImages::Warn`].
+pub fn write_files(gallery: &Gallery, config: &Config) -> Result<Vec<Unreferenced>> {
+    let translations = Arc::new(html::Translations::load(config)?);
+    let templates = html::make_templates(config, translations.clone())?;
+    let mut errors = Errors::new(&config.error_mode);
+
+    // Images and thumbnails come first because the pages depend on the thumbnails to generate placeholders.
+    let mut items = vec![];
+    for i in &gallery.image_groups {
+        let images = images::render_images(i, config);
+        items.extend(errors.check(Category::Images, images)?.unwrap_or_default());
+    }
+    write_items(items, config, &mut errors)?;
+    let previews = images::Previews::new(gallery, config, &mut errors)?;
+
+    let mut pages = vec![];
+    match config.format {
+        OutputFormat::Html => {
+            for locale in html::Locale::all(config, &translations) {
+                let gallery = &gallery.translated(locale.language);
+                for page in
+                    html::render_image_group_pages(gallery, config, &locale, &previews, &templates)
+                {
+                    pages.extend(errors.check(Category::Pages, page)?);
+                }
+                for page in html::render_tag_pages(gallery, config, &locale, &previews, &templates)
+                {
+                    pages.extend(errors.check(Category::Pages, page)?);
+                }
+                for page in html::render_pages(gallery, config, &locale, &templates) {
+                    pages.extend(errors.check(Category::Pages, page)?);
+                }
+                let overview = html::render_overview_html(
+                    gallery,
+                    config,
+                    &locale,
+                    &previews,
+                    &templates,
+                    &mut errors,
+                );
+                pages.extend(errors.check(Category::Pages, overview)?.unwrap_or_default());
+            }
+        }
+        OutputFormat::Json => {
+            let json =
+                json::render_gallery_json(gallery, config, &translations, &previews, &mut errors);
+            pages.extend(errors.check(Category::Pages, json)?);
         }
-        items
-    };
+    }
+    write_items(pages, config, &mut errors)?;
 
-    // Write items in parallel to maximize throughput.
-    items
-        .into_par_iter()
-        .map(|item| item.write(config))
-        .collect::<Result<Vec<_>>>()?;
+    if config.format == OutputFormat::Html {
+        let result = write_static(config);
+        errors.check(Category::Assets, result)?;
+    }
+    errors.into_result()
+}
 
-    // The overview has to come last because it depends on the thumbnail images to generate placeholders.
-    html::render_overview_html(gallery, config, &templates)?.write(config)?;
+/// Whether the image group gets its own HTML page.
+///
+/// Image groups without a markdown file only need a page if the overview links to it,
+/// see [`OverviewLayout::Covers`].
+fn has_page(image_group: &ImageGroup, config: &Config) -> bool {
+    image_group.markdown_file.is_some() || config.overview_layout == OverviewLayout::Covers
+}
+
+/// The thumbnail type of the overview and the tag pages.
+fn small_thumbnail(config: &Config) -> ThumbnailType {
+    match config.image_layout {
+        ImageLayout::Grid => ThumbnailType::Small,
+        ImageLayout::Justified | ImageLayout::Masonry => ThumbnailType::SmallUncropped,
+    }
+}
 
-    write_static(config)
+/// The thumbnail type of the image group pages.
+fn large_thumbnail(config: &Config) -> ThumbnailType {
+    match config.image_layout {
+        ImageLayout::Grid => ThumbnailType::Large,
+        ImageLayout::Justified | ImageLayout::Masonry => ThumbnailType::LargeUncropped,
+    }
+}
+
+/// Writes items in parallel to maximize throughput.
+fn write_items(
+    items: Vec<Box<dyn Item + Send>>,
+    config: &Config,
+    errors: &mut Errors,
+) -> Result<()> {
+    match config.error_mode {
+        ErrorMode::FailFast => {
+            items
+                .into_par_iter()
+                .map(|item| item.write(config))
+                .collect::<Result<Vec<_>>>()?;
+        }
+        ErrorMode::KeepGoing => {
+            for (category, result) in items
+                .into_par_iter()
+                .map(|item| (item.category(), item.write(config)))
+                .collect::<Vec<_>>()
+            {
+                errors.check(category, result)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collects errors in [`ErrorMode::KeepGoing`] mode.
+///
+/// In [`ErrorMode::FailFast`] mode, the first error is returned immediately instead.
+/// Also collects the images left out of image group pages in both modes.
+struct Errors<'a> {
+    mode: &'a ErrorMode,
+    errors: Vec<(Category, Error)>,
+    unreferenced: Vec<Unreferenced>,
+}
+
+impl<'a> Errors<'a> {
+    fn new(mode: &'a ErrorMode) -> Self {
+        Errors {
+            mode,
+            errors: Vec::new(),
+            unreferenced: Vec::new(),
+        }
+    }
+
+    /// Records images that are left out of the page of an image group.
+    ///
+    /// Languages without a translation of the markdown file leave out the same images, so they
+    /// are recorded only once.
+    fn unreferenced(&mut self, markdown_file: &Path, images: Vec<String>) {
+        let unreferenced = Unreferenced {
+            path: markdown_file.to_owned(),
+            images,
+        };
+        if !self.unreferenced.contains(&unreferenced) {
+            self.unreferenced.push(unreferenced);
+        }
+    }
+
+    /// Returns the value of `result` or records its error.
+    /// The return value is [`None`] if the error was recorded.
+    fn check<T>(&mut self, category: Category, result: Result<T>) -> Result<Option<T>> {
+        match (result, self.mode) {
+            (Ok(value), _) => Ok(Some(value)),
+            (Err(e), ErrorMode::FailFast) => Err(e),
+            (Err(e), ErrorMode::KeepGoing) => {
+                let e = Error::from_anyhow(e, Stage::Output);
+                // The same problem can show up on several pages, for example on the overview and
+                // on the image group page. Report it only once.
+                let message = format!("{:#}", e);
+                if !self
+                    .errors
+                    .iter()
+                    .any(|(c, other)| *c == category && format!("{:#}", other) == message)
+                {
+                    self.errors.push((category, e));
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns an [`Error::Collected`] error of all recorded errors, or the unreferenced images
+    /// if there are no errors.
+    fn into_result(mut self) -> Result<Vec<Unreferenced>> {
+        if self.errors.is_empty() {
+            return Ok(self.unreferenced);
+        }
+        self.errors.sort_by_key(|(category, _)| *category);
+        Err(Error::Collected(self.errors).into())
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Category::Pages => "HTML pages",
+            Category::Images => "Images",
+            Category::Thumbnails => "Thumbnails",
+            Category::Assets => "Static assets",
+        })
+    }
 }
 
 /// Writes static assets such as CSS and Javascript files to disk.
@@ -69,13 +254,18 @@ fn write_static(config: &Config) -> Result<()> {
             "js/wheel-zoom.min.js",
             include_str!("../templates/wheel-zoom.min.js"),
         ),
+        ("js/lightbox.js", include_str!("../templates/lightbox.js")),
         ("js/lazyload.js", include_str!("../templates/lazyload.js")),
+        ("js/search.js", include_str!("../templates/search.js")),
     ] {
         let path = &config.output_path.join(path);
         match config.run_mode {
             RunMode::Normal => {
                 create_parent_directories(path)?;
-                fs::write(path, content).path_context("Failed to write asset", path)?;
+                fs::write(path, content).map_err(|source| Error::Write {
+                    path: path.clone(),
+                    source,
+                })?;
             }
             RunMode::DryRun => {
                 println!("Static: \"{}\"", path.to_string_lossy());
@@ -94,5 +284,11 @@ fn create_parent_directories(path: &Path) -> Result<()> {
     let dir = path
         .parent()
         .path_context("Could not determine parent directory", path)?;
-    fs::create_dir_all(dir).path_context("Failed to create directory", dir)
+    fs::create_dir_all(dir).map_err(|source| {
+        Error::Write {
+            path: dir.to_owned(),
+            source,
+        }
+        .into()
+    })
 }
@@ -1,64 +1,383 @@
 //! Writes the HTML pages that make up the gallery.
 //!
 //! Currently, this is
-//! * an overview page showing all the images,
-//! * one page per image group for image groups with markdown files.
+//! * an overview showing all the images, split into pages,
+//! * archive pages per year and optionally per month,
+//! * one page per image group for image groups with markdown files,
+//! * one page per tag listing the images with this tag,
+//! * one page per markdown file in the `pages` directory.
+//!
+//! The overview and archive pages embed a search index for their search box.
+//!
+//! Multilingual galleries get all of these pages once per language, see [`locale`].
+mod helpers;
+mod layout;
+mod locale;
 mod markdown;
 
-use super::{Config, GalleryOrder, Item, RunMode, create_parent_directories};
+pub(super) use locale::{Locale, Translations};
+
+use super::{
+    Category, Config, Errors, GalleryOrder, ImageLayout, Item, OverviewLayout, RunMode,
+    UnreferencedImages, create_parent_directories, has_page, large_thumbnail, small_thumbnail,
+};
+use locale::LocaleData;
 
-use crate::error::{PathErrorContext, path_error};
-use crate::model::{Gallery, Image, ImageGroup, ThumbnailType};
-use crate::output::images::generate_placeholder;
+use crate::error::{Error, PathErrorContext, path_error};
+use crate::model::{
+    DateRange, Gallery, Image, ImageGroup, Page, ThumbnailType, natural_cmp, tag_key, tag_url,
+};
+use crate::output::images::Previews;
 
 use anyhow::{Context, Result};
 use handlebars::Handlebars;
 use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 pub(super) struct Templates<'a>(Handlebars<'a>);
 
-pub(super) fn make_templates<'a>() -> Result<Templates<'a>> {
-    let mut handlebars = Handlebars::new();
-    handlebars.set_strict_mode(true);
-    handlebars.register_template_string(
+/// The built-in templates, one per kind of page.
+const TEMPLATES: [(&str, &str); 4] = [
+    (
         "overview",
         include_str!("../../templates/overview.handlebars"),
-    )?;
-    handlebars.register_template_string(
+    ),
+    (
         "image_group",
         include_str!("../../templates/image_group.handlebars"),
-    )?;
+    ),
+    ("tag", include_str!("../../templates/tag.handlebars")),
+    ("page", include_str!("../../templates/page.handlebars")),
+];
+
+/// The built-in partials shared by the templates, for example `{{> footer}}`.
+const PARTIALS: [(&str, &str); 5] = [
+    (
+        "head",
+        include_str!("../../templates/partials/head.handlebars"),
+    ),
+    (
+        "footer",
+        include_str!("../../templates/partials/footer.handlebars"),
+    ),
+    (
+        "navigation",
+        include_str!("../../templates/partials/navigation.handlebars"),
+    ),
+    (
+        "lightbox",
+        include_str!("../../templates/partials/lightbox.handlebars"),
+    ),
+    (
+        "date",
+        include_str!("../../templates/partials/date.handlebars"),
+    ),
+];
+
+/// Registers the templates, the partials, and the helpers, see [`helpers`].
+///
+/// The files of [`Config::theme`] replace the built-in templates and partials of the same name.
+pub(super) fn make_templates<'a>(
+    config: &Config,
+    translations: Arc<Translations>,
+) -> Result<Templates<'a>> {
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    helpers::register(&mut handlebars, config, translations);
+    for (name, partial) in PARTIALS {
+        handlebars.register_partial(name, partial)?;
+    }
+    for (name, template) in TEMPLATES {
+        handlebars.register_template_string(name, template)?;
+    }
+    if let Some(theme) = &config.theme {
+        register_theme(&mut handlebars, theme)?;
+    }
     Ok(Templates(handlebars))
 }
 
-/// Renders the overview page into an [`Item`].
+/// Registers the templates of a theme directory, for example `overview.handlebars`, and its
+/// partials in the `partials` subdirectory.
+///
+/// Partials can have any name, but templates must replace one of the built-in [`TEMPLATES`].
+fn register_theme(handlebars: &mut Handlebars, theme: &Path) -> Result<()> {
+    if !theme.is_dir() {
+        return Err(path_error("Theme directory does not exist", theme));
+    }
+    for (name, _) in TEMPLATES {
+        let path = theme.join(format!("{}.handlebars", name));
+        if path.is_file() {
+            let template =
+                fs::read_to_string(&path).path_context("Failed to read template", &path)?;
+            handlebars
+                .register_template_string(name, template)
+                .path_context("Failed to parse template", &path)?;
+        }
+    }
+    let partials = theme.join("partials");
+    if !partials.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(&partials).path_context("Failed to open directory", &partials)? {
+        let path = entry
+            .path_context("Failed to read the contents of directory", &partials)?
+            .path();
+        if path.extension().is_none_or(|e| e != "handlebars") {
+            continue;
+        }
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .path_context("Failed to decode file name as UTF-8", &path)?
+            .to_owned();
+        let partial = fs::read_to_string(&path).path_context("Failed to read partial", &path)?;
+        handlebars
+            .register_partial(&name, partial)
+            .path_context("Failed to parse partial", &path)?;
+    }
+    Ok(())
+}
+
+/// Renders the overview and the archive pages into [`Item`]s.
+///
+/// The overview starts at `index.html` and lists all image groups. The archive pages list the
+/// image groups of a year (`2021.html`) or of a month (`2021-01.html`).
+/// Both are split into pages of [`Config::page_size`] image groups (`2021-page-2.html`).
+/// The introduction from [`Gallery::index_file`] is only shown on the first page of the overview.
+///
+/// Image groups that fail to render are left out of the overview if `errors` collects errors.
 pub(super) fn render_overview_html(
     gallery: &Gallery,
     config: &Config,
+    locale: &Locale,
+    previews: &Previews,
     templates: &Templates,
-) -> Result<Box<dyn Item + Send>> {
-    let data = GalleryData {
+    errors: &mut Errors,
+) -> Result<Vec<Box<dyn Item + Send>>> {
+    let data = gallery_data(gallery, config, locale, previews, errors)?;
+    // Every page embeds the search index of its own image groups, so that the search box works
+    // without a server.
+    let mut search = HashMap::new();
+    for group in &gallery.image_groups {
+        let entry = SearchEntry::from_image_group(group, config, locale);
+        if let Some(entry) = errors.check(Category::Pages, entry)? {
+            search.insert(entry.url.clone(), entry);
+        }
+    }
+    // The archives appear in the order of the image groups, so they respect the gallery order.
+    let mut years = Vec::new();
+    let mut months = Vec::new();
+    for group in &data.image_groups {
+        let (year, month) = archives_of(&group.date_range);
+        if !years.contains(&year) {
+            years.push(year);
+        }
+        if let Some(month) = month.filter(|m| config.month_archives && !months.contains(m)) {
+            months.push(month);
+        }
+    }
+    let mut res = Vec::<Box<dyn Item + Send>>::new();
+    for archive in [None]
+        .into_iter()
+        .chain(years.iter().chain(&months).map(Some))
+    {
+        let groups = data
+            .image_groups
+            .iter()
+            .filter(|g| archive.is_none_or(|a| in_archive(&g.date_range, a)))
+            .collect::<Vec<_>>();
+        let pages = match config.page_size {
+            Some(page_size) if !groups.is_empty() => groups.chunks(page_size.get()).collect(),
+            _ => vec![&groups[..]],
+        };
+        for (i, image_groups) in pages.iter().enumerate() {
+            let url = listing_url(archive, i + 1);
+            let page = OverviewData {
+                title: &data.title,
+                footer: &data.footer,
+                tags: &data.tags,
+                menu: &data.pages,
+                intro: data
+                    .intro
+                    .as_deref()
+                    .filter(|_| archive.is_none() && i == 0),
+                archive: archive.map(|a| a.to_string()),
+                years: archive_links(&years, archive, |_| true),
+                // Only the months of the current year.
+                months: archive_links(&months, archive, |m| {
+                    archive.is_some_and(|a| a.first_day().year() == m.first_day().year())
+                }),
+                image_groups,
+                search: image_groups
+                    .iter()
+                    .filter_map(|g| search.get(&g.url))
+                    .collect(),
+                pagination: pagination(archive, i + 1, pages.len()),
+                lightbox: match config.overview_layout {
+                    OverviewLayout::Covers => Vec::new(),
+                    _ => LightboxImageData::from_image_groups(image_groups, ""),
+                },
+                covers: config.overview_layout == OverviewLayout::Covers,
+                grid: config.image_layout == ImageLayout::Grid,
+                locale: locale.data(&url),
+            };
+            res.push(Box::new(HTMLFile {
+                content: templates
+                    .0
+                    .render("overview", &page)
+                    .with_context(|| "Failed to render overview HTML page")?,
+                output_path: locale.output_path(config, url),
+            }));
+        }
+    }
+    Ok(res)
+}
+
+/// The year archive and, if the image group is more precise than a year, the month archive of an
+/// image group, both based on the first day of the image group.
+fn archives_of(date: &DateRange) -> (DateRange, Option<DateRange>) {
+    let first_day = date.first_day();
+    let year = DateRange::Year(first_day.year());
+    match date {
+        DateRange::Year(_) => (year, None),
+        _ => (
+            year,
+            Some(DateRange::Month(first_day.year(), first_day.month())),
+        ),
+    }
+}
+
+fn in_archive(date: &DateRange, archive: &DateRange) -> bool {
+    let (year, month) = archives_of(date);
+    year == *archive || month.as_ref() == Some(archive)
+}
+
+/// The URL of a page of the overview or of an archive, relative to the base directory.
+/// Page numbers start at 1.
+fn listing_url(archive: Option<&DateRange>, page: usize) -> String {
+    let base = archive.map_or("index".to_owned(), |a| a.to_string());
+    if page == 1 {
+        format!("{}.html", base)
+    } else {
+        format!("{}-page-{}.html", base, page)
+    }
+}
+
+/// Links to the archives matching `filter`.
+/// A year archive is also current if the current archive is a month of that year.
+fn archive_links(
+    archives: &[DateRange],
+    current: Option<&DateRange>,
+    filter: impl Fn(&DateRange) -> bool,
+) -> Vec<ArchiveLinkData> {
+    archives
+        .iter()
+        .filter(|a| filter(a))
+        .map(|a| ArchiveLinkData {
+            name: a.to_string(),
+            url: listing_url(Some(a), 1),
+            current: current.is_some_and(|c| c == a || archives_of(c).0 == *a),
+        })
+        .collect()
+}
+
+/// The links to the other pages of the overview or of an archive.
+/// This is [`None`] if there is only one page.
+fn pagination(
+    archive: Option<&DateRange>,
+    page: usize,
+    page_count: usize,
+) -> Option<PaginationData> {
+    if page_count <= 1 {
+        return None;
+    }
+    Some(PaginationData {
+        pages: (1..=page_count)
+            .map(|number| PageLinkData {
+                number,
+                url: listing_url(archive, number),
+                current: number == page,
+            })
+            .collect(),
+        previous: (page > 1).then(|| listing_url(archive, page - 1)),
+        next: (page < page_count).then(|| listing_url(archive, page + 1)),
+    })
+}
+
+/// Collects the data of the overview page.
+///
+/// Image groups that fail to render are left out if `errors` collects errors.
+/// Images left out of image group pages are reported to `errors`, see [`UnreferencedImages::Warn`].
+pub(super) fn gallery_data(
+    gallery: &Gallery,
+    config: &Config,
+    locale: &Locale,
+    previews: &Previews,
+    errors: &mut Errors,
+) -> Result<GalleryData> {
+    Ok(GalleryData {
         title: config.page_title.clone(),
         footer: config.page_footer.clone(),
-        image_groups: image_groups_sorted(gallery, config)?,
-    };
-    Ok(Box::new(HTMLFile {
-        content: templates
-            .0
-            .render("overview", &data)
-            .with_context(|| "Failed to render overview HTML page")?,
-        output_path: config.output_path.join("index.html"),
-    }))
+        image_groups: image_groups_sorted(gallery, config, locale, previews, errors)?,
+        tags: tag_cloud(gallery)?,
+        pages: menu(gallery)?,
+        intro: gallery
+            .index_file
+            .as_ref()
+            .map(|file| {
+                let context = markdown::Context {
+                    gallery,
+                    image_group: None,
+                    base: "",
+                    root: locale.root(),
+                    config,
+                };
+                markdown::to_html(file, Vec::new(), &context).map(|res| res.html)
+            })
+            .transpose()?,
+    })
 }
 
-fn image_groups_sorted(gallery: &Gallery, config: &Config) -> Result<Vec<ImageGroupData>> {
-    let mut image_groups = gallery
-        .image_groups
-        .iter()
-        .map(|group| ImageGroupData::from_image_group(config, group, &ThumbnailType::Small))
-        .collect::<Result<Vec<_>>>()?;
+/// Links to the standalone pages, shown as a menu on every page.
+fn menu(gallery: &Gallery) -> Result<Vec<LinkData>> {
+    gallery.pages.iter().map(LinkData::page).collect()
+}
+
+fn image_groups_sorted(
+    gallery: &Gallery,
+    config: &Config,
+    locale: &Locale,
+    previews: &Previews,
+    errors: &mut Errors,
+) -> Result<Vec<ImageGroupData>> {
+    let mut res = Vec::new();
+    for group in sorted(gallery, config) {
+        let data = ImageGroupData::from_image_group(
+            config,
+            locale,
+            previews,
+            gallery,
+            group,
+            &small_thumbnail(config),
+        );
+        if let Some(data) = errors.check(Category::Pages, data)? {
+            if let (Some(markdown_file), false) =
+                (&group.markdown_file, data.unreferenced.is_empty())
+            {
+                errors.unreferenced(markdown_file, data.unreferenced.clone());
+            }
+            res.push(data);
+        }
+    }
+    Ok(res)
+}
+
+/// Sorts the image groups in the configured order.
+fn sorted<'a>(gallery: &'a Gallery, config: &Config) -> Vec<&'a ImageGroup> {
+    let mut image_groups = gallery.image_groups.iter().collect::<Vec<_>>();
     image_groups.sort_by(|lhs, rhs| {
         if lhs.date != rhs.date {
             match config.order {
@@ -66,31 +385,274 @@ fn image_groups_sorted(gallery: &Gallery, config: &Config) -> Result<Vec<ImageGr
                 GalleryOrder::MostRecentFirst => rhs.date.cmp(&lhs.date),
             }
         } else {
-            lhs.title.cmp(&rhs.title)
+            natural_cmp(&lhs.title, &rhs.title)
         }
     });
-    Ok(image_groups)
+    image_groups
 }
 
-/// Renders an image group page into an [`Item`]. This may be [`None`] if no HTML is needed.
-pub(super) fn render_image_group_html(
-    image_group: &ImageGroup,
+/// All tags of the gallery with the number of images per tag, sorted by name.
+///
+/// Tags that only differ in case or accents are merged, see [`tag_key`].
+/// The first spelling of a tag is the user-visible name.
+fn all_tags(gallery: &Gallery) -> Vec<(String, usize)> {
+    let mut tags = BTreeMap::<String, (String, usize)>::new();
+    for group in &gallery.image_groups {
+        for image in &group.images {
+            // Count every image only once per tag.
+            let mut seen = HashSet::new();
+            for tag in group.tags.iter().chain(&image.tags) {
+                let key = tag_key(tag);
+                if seen.insert(key.clone()) {
+                    tags.entry(key).or_insert_with(|| (tag.clone(), 0)).1 += 1;
+                }
+            }
+        }
+    }
+    let mut tags = tags.into_values().collect::<Vec<_>>();
+    tags.sort_by(|(lhs, _), (rhs, _)| natural_cmp(lhs, rhs));
+    tags
+}
+
+/// Collects the tag cloud of the overview page.
+///
+/// The font size of a tag grows with its number of images from 100% up to 200%.
+fn tag_cloud(gallery: &Gallery) -> Result<Vec<TagCloudData>> {
+    let tags = all_tags(gallery);
+    let max_count = tags.iter().map(|(_, count)| *count).max().unwrap_or(1);
+    tags.into_iter()
+        .map(|(name, count)| {
+            Ok(TagCloudData {
+                tag: LinkData::tag(&name)?,
+                count,
+                size: 100 + 100 * (count - 1) / max_count.saturating_sub(1).max(1),
+            })
+        })
+        .collect()
+}
+
+/// Renders one page per tag into [`Item`]s.
+///
+/// A tag page lists all images with the tag, including the images of image groups with the tag.
+pub(super) fn render_tag_pages(
+    gallery: &Gallery,
+    config: &Config,
+    locale: &Locale,
+    previews: &Previews,
+    templates: &Templates,
+) -> Vec<Result<Box<dyn Item + Send>>> {
+    all_tags(gallery)
+        .into_iter()
+        .map(|(tag, _)| render_tag_page(gallery, config, locale, previews, templates, &tag))
+        .collect()
+}
+
+fn render_tag_page(
+    gallery: &Gallery,
     config: &Config,
+    locale: &Locale,
+    previews: &Previews,
     templates: &Templates,
-) -> Result<Option<Box<dyn Item + Send>>> {
-    if image_group.markdown_file.is_none() {
-        return Ok(None);
+    tag: &str,
+) -> Result<Box<dyn Item + Send>> {
+    let mut image_groups = Vec::new();
+    for group in sorted(gallery, config) {
+        let images = group.images.iter().filter(|i| group.image_has_tag(i, tag));
+        let data = ImageGroupData::from_images(
+            config,
+            locale,
+            previews,
+            group,
+            images,
+            &small_thumbnail(config),
+        )?;
+        if !data.images.is_empty() {
+            image_groups.push(data);
+        }
     }
-    let data = ImageGroupData::from_image_group(config, image_group, &ThumbnailType::Large)?;
-    Ok(Some(Box::new(HTMLFile {
+    let data = TagPageData {
+        title: config.page_title.clone(),
+        footer: config.page_footer.clone(),
+        tag: tag.to_owned(),
+        image_groups,
+        menu: menu(gallery)?,
+        locale: locale.data(&url_to_string(&tag_url(tag))?),
+    };
+    Ok(Box::new(HTMLFile {
+        content: templates
+            .0
+            .render("tag", &data)
+            .with_context(|| format!("Failed to render HTML page for tag \"{}\"", tag))?,
+        output_path: locale.output_path(config, tag_url(tag)),
+    }))
+}
+
+/// Renders the standalone pages into [`Item`]s.
+///
+/// The markdown files of the pages can't reference images because they don't belong to an
+/// image group.
+pub(super) fn render_pages(
+    gallery: &Gallery,
+    config: &Config,
+    locale: &Locale,
+    templates: &Templates,
+) -> Vec<Result<Box<dyn Item + Send>>> {
+    gallery
+        .pages
+        .iter()
+        .map(|page| render_page(gallery, config, locale, templates, page))
+        .collect()
+}
+
+fn render_page(
+    gallery: &Gallery,
+    config: &Config,
+    locale: &Locale,
+    templates: &Templates,
+    page: &Page,
+) -> Result<Box<dyn Item + Send>> {
+    let data = PageData {
+        title: page.title.clone(),
+        gallery_title: config.page_title.clone(),
+        footer: config.page_footer.clone(),
+        content: markdown::to_html(
+            &page.markdown_file,
+            Vec::new(),
+            &markdown::Context {
+                gallery,
+                image_group: None,
+                base: "../",
+                root: locale.root(),
+                config,
+            },
+        )?
+        .html,
+        menu: menu(gallery)?,
+        locale: locale.data(&url_to_string(&page.url())?),
+    };
+    Ok(Box::new(HTMLFile {
+        content: templates
+            .0
+            .render("page", &data)
+            .with_context(|| format!("Failed to render HTML page \"{}\"", page.title))?,
+        output_path: locale.output_path(config, page.url()),
+    }))
+}
+
+/// Renders the pages of all image groups that need one into [`Item`]s, see [`has_page`].
+///
+/// Every page links to the previous and the next image group page in the gallery order and to
+/// the overview and archive pages listing the image group.
+pub(super) fn render_image_group_pages(
+    gallery: &Gallery,
+    config: &Config,
+    locale: &Locale,
+    previews: &Previews,
+    templates: &Templates,
+) -> Vec<Result<Box<dyn Item + Send>>> {
+    let image_groups = sorted(gallery, config);
+    let with_pages = image_groups
+        .iter()
+        .copied()
+        .filter(|g| has_page(g, config))
+        .collect::<Vec<_>>();
+    with_pages
+        .iter()
+        .enumerate()
+        .map(|(i, image_group)| {
+            let navigation = NavigationData {
+                previous: i
+                    .checked_sub(1)
+                    .map(|i| LinkData::image_group(with_pages[i]))
+                    .transpose()?,
+                next: with_pages
+                    .get(i + 1)
+                    .map(|g| LinkData::image_group(g))
+                    .transpose()?,
+                breadcrumbs: breadcrumbs(&image_groups, image_group, config),
+                menu: menu(gallery)?,
+            };
+            render_image_group_html(
+                gallery,
+                image_group,
+                config,
+                locale,
+                previews,
+                templates,
+                navigation,
+            )
+        })
+        .collect()
+}
+
+/// Links to the overview page and the year archive page listing the image group.
+fn breadcrumbs(
+    image_groups: &[&ImageGroup],
+    image_group: &ImageGroup,
+    config: &Config,
+) -> Vec<LinkData> {
+    let page = |groups: Vec<&&ImageGroup>| {
+        let position = groups
+            .iter()
+            .position(|g| g.path == image_group.path)
+            .unwrap_or_default();
+        config
+            .page_size
+            .map_or(1, |page_size| position / page_size.get() + 1)
+    };
+    let (year, _) = archives_of(&image_group.date);
+    vec![
+        LinkData {
+            name: config.page_title.clone(),
+            url: listing_url(None, page(image_groups.iter().collect())),
+        },
+        LinkData {
+            name: year.to_string(),
+            url: listing_url(
+                Some(&year),
+                page(
+                    image_groups
+                        .iter()
+                        .filter(|g| archives_of(&g.date).0 == year)
+                        .collect(),
+                ),
+            ),
+        },
+    ]
+}
+
+fn render_image_group_html(
+    gallery: &Gallery,
+    image_group: &ImageGroup,
+    config: &Config,
+    locale: &Locale,
+    previews: &Previews,
+    templates: &Templates,
+    navigation: NavigationData,
+) -> Result<Box<dyn Item + Send>> {
+    let image_group_data = ImageGroupData::from_image_group(
+        config,
+        locale,
+        previews,
+        gallery,
+        image_group,
+        &large_thumbnail(config),
+    )?;
+    let data = ImageGroupPageData {
+        lightbox: LightboxImageData::from_image_group_page(&image_group_data),
+        image_group: image_group_data,
+        navigation,
+        locale: locale.data(&url_to_string(&image_group.url()?)?),
+    };
+    Ok(Box::new(HTMLFile {
         content: templates.0.render("image_group", &data).with_context(|| {
             format!(
                 "Failed to render HTML page for image group \"{}\"",
                 image_group.title
             )
         })?,
-        output_path: config.output_path.join(image_group.url()?),
-    })))
+        output_path: locale.output_path(config, image_group.url()?),
+    }))
 }
 
 /// An HTML file ready to be written to disk.
@@ -105,8 +667,13 @@ impl Item for HTMLFile {
         match &config.run_mode {
             RunMode::Normal => {
                 create_parent_directories(&self.output_path)?;
-                fs::write(&self.output_path, &self.content)
-                    .path_context("Failed to write HTML file", &self.output_path)
+                fs::write(&self.output_path, &self.content).map_err(|source| {
+                    Error::Write {
+                        path: self.output_path.clone(),
+                        source,
+                    }
+                    .into()
+                })
             }
             RunMode::DryRun => {
                 println!("HTML:  \"{}\"", self.output_path.to_string_lossy());
@@ -114,14 +681,75 @@ impl Item for HTMLFile {
             }
         }
     }
+
+    fn category(&self) -> Category {
+        Category::Pages
+    }
 }
 
 /// Used in handlebars templates to describe a gallery.
 #[derive(Serialize)]
-struct GalleryData {
+pub(super) struct GalleryData {
     title: String,
     footer: Option<String>,
     image_groups: Vec<ImageGroupData>,
+    tags: Vec<TagCloudData>,
+    /// Links to the standalone pages.
+    pages: Vec<LinkData>,
+    /// The HTML of the introduction on the overview page.
+    intro: Option<String>,
+}
+
+/// Used in handlebars templates to describe a page of the overview or of an archive.
+#[derive(Serialize)]
+struct OverviewData<'a> {
+    title: &'a str,
+    footer: &'a Option<String>,
+    tags: &'a [TagCloudData],
+    menu: &'a [LinkData],
+    /// The HTML of the introduction, only on the first page of the overview.
+    intro: Option<&'a str>,
+    /// The year or month of an archive page, [`None`] on the overview.
+    archive: Option<String>,
+    years: Vec<ArchiveLinkData>,
+    months: Vec<ArchiveLinkData>,
+    image_groups: &'a [&'a ImageGroupData],
+    /// The search index of the image groups on the page.
+    search: Vec<&'a SearchEntry>,
+    pagination: Option<PaginationData>,
+    /// The images of the lightbox, empty if the overview has no image cards.
+    lightbox: Vec<LightboxImageData>,
+    /// Whether to show one card per image group instead of one card per image.
+    covers: bool,
+    /// Whether to arrange the images in a grid, see [`ImageGroupData::with_layout`].
+    grid: bool,
+    #[serde(flatten)]
+    locale: LocaleData,
+}
+
+/// Used in handlebars templates to link to an archive page.
+#[derive(Serialize)]
+struct ArchiveLinkData {
+    name: String,
+    url: String,
+    /// Whether the archive contains the current page.
+    current: bool,
+}
+
+/// Used in handlebars templates to link to the other pages of the overview or of an archive.
+#[derive(Serialize)]
+struct PaginationData {
+    pages: Vec<PageLinkData>,
+    previous: Option<String>,
+    next: Option<String>,
+}
+
+/// Used in handlebars templates to link to a single page of the overview or of an archive.
+#[derive(Serialize)]
+struct PageLinkData {
+    number: usize,
+    url: String,
+    current: bool,
 }
 
 /// Used in handlebars templates to describe an image group.
@@ -129,39 +757,395 @@ struct GalleryData {
 struct ImageGroupData {
     title: Option<String>,
     footer: Option<String>,
+    /// The date in [`Config::date_format`].
     date: String,
+    /// The first day in ISO 8601 with the precision of the date, for `<time datetime>`.
+    datetime: String,
+    /// The last day in ISO 8601 if the date is a range of days.
+    datetime_end: Option<String>,
+    #[serde(skip)]
+    date_range: DateRange,
     markdown_content: Option<String>,
     images: Vec<ImageData>,
+    /// The cover image of the image group, see [`ImageGroupData::choose_cover`].
+    cover: Option<ImageData>,
+    /// The number of images in the image group.
+    image_count: usize,
+    /// The images in rows for [`ImageLayout::Justified`].
+    justified_rows: Option<Vec<RowData>>,
+    /// The images in columns for [`ImageLayout::Masonry`].
+    masonry_columns: Option<Vec<ColumnData>>,
+    /// The headings of the markdown content, for a table of contents.
+    toc: Vec<HeadingData>,
+    /// The images left out of the markdown content, see [`UnreferencedImages::Warn`].
+    #[serde(skip)]
+    unreferenced: Vec<String>,
     url: String,
+    tags: Vec<LinkData>,
 }
 
-/// Used in handlebars templates to describe a single image.
+/// Used in handlebars templates to describe a heading in a table of contents.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct HeadingData {
+    /// The heading level from 1 to 6.
+    level: usize,
+    title: String,
+    /// The ID of the heading for deep-linking.
+    anchor: String,
+}
+
+/// Used in handlebars templates to describe an image group page.
+#[derive(Serialize)]
+struct ImageGroupPageData {
+    #[serde(flatten)]
+    image_group: ImageGroupData,
+    #[serde(flatten)]
+    navigation: NavigationData,
+    lightbox: Vec<LightboxImageData>,
+    #[serde(flatten)]
+    locale: LocaleData,
+}
+
+/// Used in handlebars templates to link an image group page to its surroundings.
+#[derive(Serialize)]
+struct NavigationData {
+    /// The previous image group page in the gallery order.
+    previous: Option<LinkData>,
+    /// The next image group page in the gallery order.
+    next: Option<LinkData>,
+    /// The overview page and the year archive page listing the image group.
+    breadcrumbs: Vec<LinkData>,
+    menu: Vec<LinkData>,
+}
+
+/// Used in handlebars templates to describe a tag page.
+#[derive(Serialize)]
+struct TagPageData {
+    title: String,
+    footer: Option<String>,
+    tag: String,
+    /// The image groups with at least one image with the tag, containing only these images.
+    image_groups: Vec<ImageGroupData>,
+    menu: Vec<LinkData>,
+    #[serde(flatten)]
+    locale: LocaleData,
+}
+
+/// Used in handlebars templates to describe a standalone page.
+#[derive(Serialize)]
+struct PageData {
+    title: String,
+    gallery_title: String,
+    footer: Option<String>,
+    /// The HTML of the markdown file.
+    content: String,
+    menu: Vec<LinkData>,
+    #[serde(flatten)]
+    locale: LocaleData,
+}
+
+/// Used in handlebars templates to link to another page, for example a tag page.
+#[derive(Clone, Serialize)]
+struct LinkData {
+    name: String,
+    /// The URL of the page, relative to the base directory.
+    url: String,
+}
+
+/// Used in handlebars templates to describe a tag in the tag cloud.
+#[derive(Serialize)]
+struct TagCloudData {
+    #[serde(flatten)]
+    tag: LinkData,
+    /// The number of images with the tag.
+    count: usize,
+    /// The font size in percent.
+    size: usize,
+}
+
+/// Used in handlebars templates to describe a row of a justified layout.
 #[derive(Serialize)]
+struct RowData {
+    images: Vec<RowImageData>,
+}
+
+/// Used in handlebars templates to describe an image in a row of a justified layout.
+#[derive(Serialize)]
+struct RowImageData {
+    #[serde(flatten)]
+    image: ImageData,
+    /// The width of the image in percent of the row width.
+    width_percent: f64,
+}
+
+/// Used in handlebars templates to describe a column of a masonry layout.
+#[derive(Serialize)]
+struct ColumnData {
+    images: Vec<ImageData>,
+}
+
+/// Used in handlebars templates to describe a single image.
+#[derive(Clone, Serialize)]
 struct ImageData {
     url: String,
     name: String,
     thumbnail: String,
     anchor: String,
     placeholder: String,
+    /// The caption from the markdown file, see [`markdown`].
+    caption: Option<String>,
+    /// The alt text from the markdown file, or the name of the image.
+    alt: String,
+    /// The width of the source image in pixels.
+    width: u32,
+    /// The height of the source image in pixels.
+    height: u32,
+    /// The tags of the image itself, without the tags of its image group.
+    tags: Vec<LinkData>,
+}
+
+/// Used in handlebars templates to describe an image in the lightbox.
+///
+/// The lightbox data is embedded as JSON with the `json` helper, see [`helpers`].
+#[derive(Debug, PartialEq, Serialize)]
+struct LightboxImageData {
+    url: String,
+    /// The date and title of the image group and the caption or name of the image.
+    title: String,
+}
+
+impl LightboxImageData {
+    /// The images of the image groups in their order. `base` is the relative path from the page
+    /// to the base directory, like the links to the images on the page.
+    fn from_image_groups(image_groups: &[&ImageGroupData], base: &str) -> Vec<LightboxImageData> {
+        image_groups
+            .iter()
+            .flat_map(|group| {
+                group.images.iter().map(|image| {
+                    let heading = match &group.title {
+                        Some(title) => format!("{}\u{2003}{}", group.date, title),
+                        None => group.date.clone(),
+                    };
+                    LightboxImageData {
+                        url: format!("{}{}", base, image.url),
+                        title: format!(
+                            "{}, {}",
+                            heading,
+                            image.caption.as_ref().unwrap_or(&image.name)
+                        ),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// The images shown on the page of the image group, in the order of the markdown file.
+    fn from_image_group_page(image_group: &ImageGroupData) -> Vec<LightboxImageData> {
+        Self::from_image_groups(&[image_group], "../")
+            .into_iter()
+            .zip(&image_group.images)
+            .filter(|(_, image)| !image_group.unreferenced.contains(&image.name))
+            .map(|(lightbox, _)| lightbox)
+            .collect()
+    }
+}
+
+/// The searchable data of an image group for the search box on the overview page.
+///
+/// The image names are not part of it because every card has its own, see
+/// `data-search-image` in the overview template. The gallery has no location data, so places
+/// are only searchable as part of the title or the text.
+#[derive(Serialize)]
+struct SearchEntry {
+    /// Identifies the cards of this image group on the overview page.
+    url: String,
+    title: String,
+    date: String,
+    /// The plain text of the markdown file.
+    text: String,
+    /// The tags of the image group and its images.
+    tags: Vec<String>,
+}
+
+impl SearchEntry {
+    fn from_image_group(
+        image_group: &ImageGroup,
+        config: &Config,
+        locale: &Locale,
+    ) -> Result<SearchEntry> {
+        Ok(SearchEntry {
+            url: url_to_string(&image_group.url()?)?,
+            title: image_group.title.clone(),
+            date: locale.format_date(&image_group.date, config.date_format),
+            text: match &image_group.markdown_file {
+                Some(markdown_file) => markdown::to_text(markdown_file, config)?,
+                None => String::new(),
+            },
+            tags: image_group
+                .tags
+                .iter()
+                .chain(image_group.images.iter().flat_map(|i| &i.tags))
+                .fold(Vec::new(), |mut tags, tag| {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                    tags
+                }),
+        })
+    }
+}
+
+impl LinkData {
+    fn image_group(image_group: &ImageGroup) -> Result<LinkData> {
+        Ok(LinkData {
+            name: image_group.title.clone(),
+            url: url_to_string(&image_group.url()?)?,
+        })
+    }
+
+    fn page(page: &Page) -> Result<LinkData> {
+        Ok(LinkData {
+            name: page.title.clone(),
+            url: url_to_string(&page.url())?,
+        })
+    }
+
+    fn tag(tag: &str) -> Result<LinkData> {
+        Ok(LinkData {
+            name: tag.to_owned(),
+            url: url_to_string(&tag_url(tag))?,
+        })
+    }
 }
 
 impl ImageGroupData {
     // Adds markdown content and reorders the images to match the markdown content.
-    fn add_markdown(self, markdown_file: &Option<PathBuf>) -> Result<Self> {
-        let Some(markdown_file) = markdown_file else {
+    fn add_markdown(
+        self,
+        gallery: &Gallery,
+        image_group: &ImageGroup,
+        config: &Config,
+        locale: &Locale,
+    ) -> Result<Self> {
+        let Some(markdown_file) = &image_group.markdown_file else {
             return Ok(self);
         };
-        let res = markdown::to_html(markdown_file, self.images)?;
+        let context = markdown::Context {
+            gallery,
+            image_group: Some(image_group),
+            base: "../",
+            root: locale.root(),
+            config,
+        };
+        let res = markdown::to_html(markdown_file, self.images, &context)?;
+        let cover = res
+            .first_image
+            .and_then(|name| res.images_seen.iter().find(|i| i.name == name).cloned());
         Ok(Self {
             markdown_content: Some(res.html.clone()),
             images: res.images_seen,
+            cover,
+            toc: res.headings,
+            unreferenced: res.unreferenced,
+            ..self
+        })
+    }
+
+    // Chooses the cover image in this order:
+    // 1. the cover image from the front matter,
+    // 2. the first image referenced in the markdown file,
+    // 3. the first image.
+    fn choose_cover(self, image_group: &ImageGroup) -> Result<Self> {
+        let Some(name) = &image_group.cover else {
+            return Ok(self);
+        };
+        let Some(cover) = self.images.iter().find(|i| i.name == *name).cloned() else {
+            let markdown_file = image_group
+                .markdown_file
+                .as_deref()
+                .unwrap_or(&image_group.path);
+            return Err(path_error(
+                &format!("Unknown cover image \"{}\" in front matter", name),
+                markdown_file,
+            ));
+        };
+        Ok(Self {
+            cover: Some(cover),
             ..self
         })
     }
 
     fn from_image_group(
         config: &Config,
+        locale: &Locale,
+        previews: &Previews,
+        gallery: &Gallery,
+        image_group: &ImageGroup,
+        thumbnail_type: &ThumbnailType,
+    ) -> Result<ImageGroupData> {
+        let data = Self::from_images(
+            config,
+            locale,
+            previews,
+            image_group,
+            image_group.images.iter(),
+            thumbnail_type,
+        )?;
+        data.add_markdown(gallery, image_group, config, locale)?
+            .choose_cover(image_group)
+            .map(|data| data.with_layout(config))
+    }
+
+    // Arranges the images according to the configured layout.
+    // The grid layout needs no preparation, the templates use `images` directly.
+    fn with_layout(self, config: &Config) -> Self {
+        let aspect_ratios = self
+            .images
+            .iter()
+            .map(|i| f64::from(i.width) / f64::from(i.height.max(1)))
+            .collect::<Vec<_>>();
+        match config.image_layout {
+            ImageLayout::Grid => self,
+            ImageLayout::Justified => {
+                let rows = layout::justified_rows(&aspect_ratios)
+                    .into_iter()
+                    .map(|row| RowData {
+                        images: row
+                            .into_iter()
+                            .map(|(i, width_percent)| RowImageData {
+                                image: self.images[i].clone(),
+                                width_percent,
+                            })
+                            .collect(),
+                    })
+                    .collect();
+                Self {
+                    justified_rows: Some(rows),
+                    ..self
+                }
+            }
+            ImageLayout::Masonry => {
+                let columns = layout::masonry_columns(&aspect_ratios)
+                    .into_iter()
+                    .map(|column| ColumnData {
+                        images: column.into_iter().map(|i| self.images[i].clone()).collect(),
+                    })
+                    .collect();
+                Self {
+                    masonry_columns: Some(columns),
+                    ..self
+                }
+            }
+        }
+    }
+
+    /// Describes a subset of the images of an image group without the markdown content.
+    fn from_images<'a>(
+        config: &Config,
+        locale: &Locale,
+        previews: &Previews,
         image_group: &ImageGroup,
+        images: impl Iterator<Item = &'a Image>,
         thumbnail_type: &ThumbnailType,
     ) -> Result<ImageGroupData> {
         // Suppress the title if it's redundant.
@@ -171,20 +1155,30 @@ impl ImageGroupData {
             } else {
                 Some(image_group.title.clone())
             };
-        let images = image_group
-            .images
-            .iter()
-            .map(|image| ImageData::from_image(image, image_group, thumbnail_type, config))
+        let images = images
+            .map(|image| {
+                ImageData::from_image(image, image_group, thumbnail_type, config, locale, previews)
+            })
             .collect::<Result<Vec<_>>>()?;
-        let data = ImageGroupData {
+        let (datetime, datetime_end) = locale::iso_date(&image_group.date);
+        Ok(ImageGroupData {
             title,
             footer: config.page_footer.clone(),
-            date: image_group.date.to_string(),
+            date: locale.format_date(&image_group.date, config.date_format),
+            datetime,
+            datetime_end,
+            date_range: image_group.date,
             markdown_content: None,
+            cover: images.first().cloned(),
+            image_count: images.len(),
+            justified_rows: None,
+            masonry_columns: None,
+            toc: Vec::new(),
+            unreferenced: Vec::new(),
             images,
             url: url_to_string(&image_group.url()?)?,
-        };
-        data.add_markdown(&image_group.markdown_file)
+            tags: tag_links(&image_group.tags)?,
+        })
     }
 }
 
@@ -194,19 +1188,32 @@ impl ImageData {
         image_group: &ImageGroup,
         thumbnail_type: &ThumbnailType,
         config: &Config,
+        locale: &Locale,
+        previews: &Previews,
     ) -> Result<ImageData> {
         let thumbnail_path = image_group.thumbnail_url(image, thumbnail_type)?;
-        let placeholder = generate_placeholder(&config.output_path.join(&thumbnail_path))?;
+        let placeholder = previews.placeholder(&config.output_path.join(&thumbnail_path));
+        let (width, height) = previews.dimensions(&image.path);
         Ok(ImageData {
-            url: url_to_string(&image_group.image_url(image)?)?,
+            // Images and thumbnails are shared by all languages.
+            url: locale.root().to_owned() + &url_to_string(&image_group.image_url(image)?)?,
             name: image.name.clone(),
-            thumbnail: url_to_string(&thumbnail_path)?,
+            thumbnail: locale.root().to_owned() + &url_to_string(&thumbnail_path)?,
             anchor: slug::slugify(&image.name),
             placeholder,
+            caption: None,
+            alt: image.name.clone(),
+            width,
+            height,
+            tags: tag_links(&image.tags)?,
         })
     }
 }
 
+fn tag_links(tags: &[String]) -> Result<Vec<LinkData>> {
+    tags.iter().map(|tag| LinkData::tag(tag)).collect()
+}
+
 /// Converts a URL from path form into a string.
 /// The path components will be joined by slashes.
 fn url_to_string(url: &Path) -> Result<String> {
@@ -220,8 +1227,12 @@ fn url_to_string(url: &Path) -> Result<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::url_to_string;
+    use super::{Translations, archives_of, listing_url, make_templates, url_to_string};
+    use crate::config::{Config, DateFormat};
+    use crate::model::DateRange;
     use std::path::PathBuf;
+    use std::sync::Arc;
+    use time::{Date, Month};
 
     #[test]
     fn test_url() {
@@ -235,4 +1246,67 @@ mod tests {
             "foo/bar"
         );
     }
+
+    #[test]
+    fn test_listing_url() {
+        assert_eq!(listing_url(None, 1), "index.html");
+        assert_eq!(listing_url(None, 2), "index-page-2.html");
+        let month = DateRange::Month(2021, Month::January);
+        assert_eq!(listing_url(Some(&month), 1), "2021-01.html");
+        assert_eq!(listing_url(Some(&month), 3), "2021-01-page-3.html");
+    }
+
+    #[test]
+    fn test_date_helper() {
+        let config = Config::builder("build")
+            .date_format(DateFormat::Long)
+            .build();
+        let translations = Arc::new(Translations::load(&config).unwrap());
+        let templates = make_templates(&config, translations).unwrap();
+        let render = |template: &str, lang: &str| {
+            templates
+                .0
+                .render_template(
+                    template,
+                    &serde_json::json!({ "lang": lang, "datetime": "2021-03-02" }),
+                )
+                .unwrap()
+        };
+        assert_eq!(render("{{format_date datetime}}", "en"), "2 March 2021");
+        assert_eq!(render("{{format_date datetime}}", "de"), "2. März 2021");
+        assert_eq!(
+            render(r#"{{format_date datetime "numeric"}}"#, "de"),
+            "02.03.2021"
+        );
+        assert_eq!(
+            render(r#"{{format_date "2021-03" "long"}}"#, "de"),
+            "März 2021"
+        );
+        assert!(
+            templates
+                .0
+                .render_template(
+                    r#"{{format_date "2021-03" "short"}}"#,
+                    &serde_json::json!({})
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_archives_of() {
+        assert_eq!(
+            archives_of(&DateRange::Year(2021)),
+            (DateRange::Year(2021), None)
+        );
+        let first = Date::from_calendar_date(2021, Month::December, 30).unwrap();
+        let last = Date::from_calendar_date(2022, Month::January, 2).unwrap();
+        assert_eq!(
+            archives_of(&DateRange::Days(first, last)),
+            (
+                DateRange::Year(2021),
+                Some(DateRange::Month(2021, Month::December))
+            )
+        );
+    }
 }
@@ -0,0 +1,136 @@
+//! Handlebars helpers available to all templates, including the templates of a theme.
+//!
+//! * `t` translates a fixed string into the language of the page, see [`super::locale`].
+//! * `format_date` formats an ISO 8601 date like `{{format_date datetime "long"}}`.
+//! * `relative_url` joins its parameters into a URL relative to the current page, for example
+//!   `{{relative_url root "css/style.css"}}` for a static asset or `{{relative_url url}}` for
+//!   another page of the same language.
+//! * `pluralize` chooses between the singular and the plural form, for example
+//!   `{{pluralize image_count "photo" "photos"}}`.
+//! * `json` encodes a value as JSON for `<script>` elements, for example `{{{json images}}}`.
+use super::Config;
+use super::locale::{DateHelper, TranslateHelper, Translations};
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason,
+    handlebars_helper, html_escape,
+};
+use std::sync::Arc;
+
+/// Registers all helpers.
+pub(super) fn register(
+    handlebars: &mut Handlebars,
+    config: &Config,
+    translations: Arc<Translations>,
+) {
+    handlebars.register_helper(
+        "format_date",
+        Box::new(DateHelper {
+            format: config.date_format,
+            translations: translations.clone(),
+        }),
+    );
+    handlebars.register_helper("t", Box::new(TranslateHelper { translations }));
+    handlebars.register_helper("relative_url", Box::new(relative_url));
+    handlebars.register_helper("pluralize", Box::new(pluralize));
+    handlebars.register_helper("json", Box::new(json));
+}
+
+/// Joins the parameters and prefixes them with the path from the current page to the base
+/// directory of its language, `base` in [`super::locale::LocaleData`].
+fn relative_url(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let mut url = ctx
+        .data()
+        .get("base")
+        .and_then(|b| b.as_str())
+        .unwrap_or_default()
+        .to_owned();
+    for param in h.params() {
+        url += param
+            .value()
+            .as_str()
+            .ok_or(RenderErrorReason::InvalidParamType("string"))?;
+    }
+    out.write(&html_escape(&url))?;
+    Ok(())
+}
+
+handlebars_helper!(pluralize: |count: u64, singular: str, plural: str| {
+    if count == 1 { singular } else { plural }
+});
+
+handlebars_helper!(json: |value: Json| escape_json(&value.to_string()));
+
+/// Escapes JSON so that it can't end a surrounding `<script>` element.
+///
+/// The escaped characters only appear inside JSON strings, where the escape sequences have the
+/// same meaning.
+pub(super) fn escape_json(value: &str) -> String {
+    value
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Translations, escape_json, register};
+    use crate::config::Config;
+    use handlebars::Handlebars;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn render(template: &str, data: serde_json::Value) -> String {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+        let config = Config::builder("build").build();
+        let translations = Arc::new(Translations::load(&config).unwrap());
+        register(&mut handlebars, &config, translations);
+        handlebars.render_template(template, &data).unwrap()
+    }
+
+    #[test]
+    fn test_relative_url() {
+        let data = json!({ "base": "../", "root": "../", "url": "html/a b.html" });
+        assert_eq!(
+            render(r#"{{relative_url root "css/style.css"}}"#, data.clone()),
+            "../../css/style.css"
+        );
+        assert_eq!(render("{{relative_url url}}", data), "../html/a b.html");
+        assert_eq!(
+            render(r#"{{relative_url "index.html"}}"#, json!({})),
+            "index.html"
+        );
+    }
+
+    #[test]
+    fn test_pluralize() {
+        let template = r#"{{count}} {{pluralize count "photo" "photos"}}"#;
+        assert_eq!(render(template, json!({ "count": 1 })), "1 photo");
+        assert_eq!(render(template, json!({ "count": 0 })), "0 photos");
+        // Helpers can be combined, for example to translate the result.
+        assert_eq!(
+            render(
+                r#"{{t (pluralize count "photo" "photos")}}"#,
+                json!({ "count": 2, "lang": "de" })
+            ),
+            "Fotos"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let data = json!({ "value": { "title": "\"A\" </script> & \\" } });
+        assert_eq!(
+            render("{{{json value}}}", data),
+            r#"{"title":"\"A\" \u003c/script\u003e \u0026 \\"}"#
+        );
+        assert_eq!(escape_json("[1]"), "[1]");
+    }
+}
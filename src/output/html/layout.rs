@@ -0,0 +1,93 @@
+//! Computes image layouts at build time, so pages render without layout shift.
+//!
+//! The layouts only depend on the aspect ratios of the images. The templates scale them to the
+//! page width with CSS.
+
+/// The aspect ratio of a full row in a justified layout, for example 1200px wide and 250px high.
+const ROW_ASPECT_RATIO: f64 = 4.8;
+
+/// The number of columns in a masonry layout.
+const MASONRY_COLUMNS: usize = 3;
+
+/// Splits images into rows of equal height that fill the row width.
+///
+/// Returns for every row the indices of its images together with their widths in percent of the
+/// row width. The last row is not stretched if it's not full.
+pub(super) fn justified_rows(aspect_ratios: &[f64]) -> Vec<Vec<(usize, f64)>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut row_aspect_ratio = 0.0;
+    for (i, &aspect_ratio) in aspect_ratios.iter().enumerate() {
+        row.push(i);
+        row_aspect_ratio += aspect_ratio;
+        if row_aspect_ratio >= ROW_ASPECT_RATIO {
+            rows.push(widths(&row, aspect_ratios, row_aspect_ratio));
+            row.clear();
+            row_aspect_ratio = 0.0;
+        }
+    }
+    if !row.is_empty() {
+        rows.push(widths(&row, aspect_ratios, ROW_ASPECT_RATIO));
+    }
+    rows
+}
+
+fn widths(row: &[usize], aspect_ratios: &[f64], row_aspect_ratio: f64) -> Vec<(usize, f64)> {
+    row.iter()
+        .map(|&i| (i, round(aspect_ratios[i] / row_aspect_ratio * 100.0)))
+        .collect()
+}
+
+/// Rounds to three decimal places to keep the HTML small.
+fn round(x: f64) -> f64 {
+    (x * 1000.0).round() / 1000.0
+}
+
+/// Distributes images over columns of equal width.
+///
+/// Every image goes into the currently shortest column, so the columns end up with similar
+/// heights. Returns the indices of the images in every column.
+pub(super) fn masonry_columns(aspect_ratios: &[f64]) -> Vec<Vec<usize>> {
+    let mut columns = vec![Vec::new(); MASONRY_COLUMNS.min(aspect_ratios.len())];
+    // The height of every column relative to the column width.
+    let mut heights = vec![0.0_f64; columns.len()];
+    for (i, aspect_ratio) in aspect_ratios.iter().enumerate() {
+        let shortest = (0..columns.len())
+            .min_by(|&a, &b| heights[a].total_cmp(&heights[b]))
+            .expect("there is at least one column");
+        columns[shortest].push(i);
+        heights[shortest] += 1.0 / aspect_ratio;
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{justified_rows, masonry_columns};
+
+    #[test]
+    fn test_justified_rows() {
+        let rows = justified_rows(&[1.5, 1.5, 1.0, 1.5, 0.5, 1.5]);
+        assert_eq!(
+            rows,
+            vec![
+                // 1.5 + 1.5 + 1.0 + 1.5 = 5.5 fills the row.
+                vec![(0, 27.273), (1, 27.273), (2, 18.182), (3, 27.273)],
+                // The last row keeps the height of a full row.
+                vec![(4, 10.417), (5, 31.25)],
+            ]
+        );
+        assert!(justified_rows(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_masonry_columns() {
+        // A portrait image makes its column taller, so the next images go elsewhere.
+        assert_eq!(
+            masonry_columns(&[0.5, 1.5, 1.5, 1.5, 1.5]),
+            vec![vec![0], vec![1, 3], vec![2, 4]]
+        );
+        assert_eq!(masonry_columns(&[1.0]), vec![vec![0]]);
+        assert!(masonry_columns(&[]).is_empty());
+    }
+}
@@ -0,0 +1,650 @@
+//! Localizes the HTML pages for multilingual galleries.
+//!
+//! Every language gets its own tree of HTML pages with the same URLs. The pages of the default
+//! language are in the output directory, the pages of every other language in a subdirectory
+//! named after the language, for example `de/index.html`. Images, thumbnails, and static assets
+//! exist only once in the output directory and are shared by all languages.
+//!
+//! Templates translate their fixed strings with the `t` helper, for example `{{t "Next"}}`,
+//! and format dates with the `format_date` helper, for example `{{format_date datetime "long"}}`.
+//! The translations are JSON files like `de.json`, see [`Translations`].
+use super::Config;
+
+use crate::config::DateFormat;
+use crate::error::PathErrorContext;
+use crate::model::DateRange;
+
+use anyhow::Result;
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+    html_escape,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use time::{Date, Month};
+
+/// The language of galleries without configured languages.
+const DEFAULT_LANGUAGE: &str = "en-US";
+
+/// The built-in translations by language code.
+const BUILTIN_TRANSLATIONS: [(&str, &str); 1] = [(
+    "de",
+    include_str!("../../../templates/translations/de.json"),
+)];
+
+/// The translation of the templates into one language.
+///
+/// The JSON file looks like the built-in `templates/translations/de.json`. All keys are
+/// optional, missing parts fall back to English.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Translation {
+    /// The names of the months, starting with January.
+    months: Option<[String; 12]>,
+    dates: DatePatterns,
+    /// The translations of the fixed strings of the templates, see [`TranslateHelper`].
+    strings: HashMap<String, String>,
+}
+
+/// How a language writes dates in [`DateFormat::Numeric`] and [`DateFormat::Long`].
+///
+/// The patterns contain the placeholders `{d}` and `{dd}` for the day, `{m}`, `{mm}`, and
+/// `{month}` for the month, and `{yyyy}` for the year. `{dd}` and `{mm}` have two digits and
+/// `{month}` is the name of the month.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DatePatterns {
+    /// English: `{yyyy}-{mm}-{dd}`.
+    numeric_day: Option<String>,
+    /// English: `{yyyy}-{mm}`.
+    numeric_month: Option<String>,
+    /// English: `{d} {month} {yyyy}`.
+    long_day: Option<String>,
+    /// English: `{month} {yyyy}`.
+    long_month: Option<String>,
+}
+
+impl DatePatterns {
+    /// Replaces the patterns that `other` has.
+    fn extend(&mut self, other: DatePatterns) {
+        let DatePatterns {
+            numeric_day,
+            numeric_month,
+            long_day,
+            long_month,
+        } = other;
+        for (pattern, other) in [
+            (&mut self.numeric_day, numeric_day),
+            (&mut self.numeric_month, numeric_month),
+            (&mut self.long_day, long_day),
+            (&mut self.long_month, long_month),
+        ] {
+            if other.is_some() {
+                *pattern = other;
+            }
+        }
+    }
+}
+
+/// The translations of the templates into all languages except English, by language code.
+///
+/// The built-in translations can be extended and overridden by the `translations` directory of
+/// [`Config::theme`], which contains one JSON file per language like `de.json` or `fr.json`.
+#[derive(Debug, Default)]
+pub(in crate::output) struct Translations(HashMap<String, Translation>);
+
+impl Translations {
+    /// Reads the built-in translations and the translations of the theme.
+    pub fn load(config: &Config) -> Result<Translations> {
+        let mut res = Translations::default();
+        for (language, json) in BUILTIN_TRANSLATIONS {
+            res.add(
+                language,
+                serde_json::from_str(json).expect("the built-in translations are valid"),
+            );
+        }
+        let Some(dir) = config.theme.as_ref().map(|t| t.join("translations")) else {
+            return Ok(res);
+        };
+        if !dir.is_dir() {
+            return Ok(res);
+        }
+        for entry in fs::read_dir(&dir).path_context("Failed to open directory", &dir)? {
+            let path = entry
+                .path_context("Failed to read the contents of directory", &dir)?
+                .path();
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let language = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .path_context("Failed to decode file name as UTF-8", &path)?
+                .to_owned();
+            let json =
+                fs::read_to_string(&path).path_context("Failed to read translations", &path)?;
+            let translation =
+                serde_json::from_str(&json).path_context("Failed to parse translations", &path)?;
+            res.add(&language, translation);
+        }
+        Ok(res)
+    }
+
+    /// Adds a translation, replacing the parts of existing translations that it has.
+    fn add(&mut self, language: &str, translation: Translation) {
+        let existing = self.0.entry(language.to_owned()).or_default();
+        if translation.months.is_some() {
+            existing.months = translation.months;
+        }
+        existing.dates.extend(translation.dates);
+        existing.strings.extend(translation.strings);
+    }
+
+    /// Looks up a part of the translation into a language like `de-AT`, or else into its
+    /// primary language `de`.
+    fn get<'a, T: ?Sized>(
+        &'a self,
+        language: &str,
+        part: impl Fn(&'a Translation) -> Option<&'a T>,
+    ) -> Option<&'a T> {
+        let primary = language.split('-').next().unwrap_or(language);
+        [language, primary]
+            .into_iter()
+            .filter_map(|l| self.0.get(l))
+            .find_map(part)
+    }
+
+    /// Translates a fixed string of the templates.
+    /// Strings without a translation are returned unchanged.
+    fn translate<'a>(&'a self, language: &str, text: &'a str) -> &'a str {
+        self.get(language, |t| t.strings.get(text))
+            .map_or(text, String::as_str)
+    }
+
+    /// Formats a date the way readers of the language expect it.
+    ///
+    /// Languages without month names or date patterns use the English ones.
+    fn format_date(&self, language: &str, date: &DateRange, format: DateFormat) -> String {
+        let pattern = |day: bool| {
+            let (pattern, english): (fn(&DatePatterns) -> Option<&String>, _) = match (format, day)
+            {
+                (DateFormat::Long, true) => (|p| p.long_day.as_ref(), "{d} {month} {yyyy}"),
+                (DateFormat::Long, false) => (|p| p.long_month.as_ref(), "{month} {yyyy}"),
+                (_, true) => (|p| p.numeric_day.as_ref(), "{yyyy}-{mm}-{dd}"),
+                (_, false) => (|p| p.numeric_month.as_ref(), "{yyyy}-{mm}"),
+            };
+            self.get(language, |t| pattern(&t.dates))
+                .map_or(english, String::as_str)
+        };
+        let fill = |pattern: &str, year: i32, month: Month, day: Option<u8>| {
+            let month_name = self
+                .get(language, |t| t.months.as_ref())
+                .map_or(ENGLISH_MONTHS[month as usize - 1], |m| {
+                    &m[month as usize - 1]
+                });
+            let mut res = pattern
+                .replace("{yyyy}", &format!("{:04}", year))
+                .replace("{mm}", &format!("{:02}", month as u8))
+                .replace("{m}", &(month as u8).to_string());
+            if let Some(day) = day {
+                res = res
+                    .replace("{dd}", &format!("{:02}", day))
+                    .replace("{d}", &day.to_string());
+            }
+            // Last, so that month names can't contain placeholders.
+            res.replace("{month}", month_name)
+        };
+        let day = |d: &Date| match format {
+            DateFormat::Iso => d.to_string(),
+            _ => fill(pattern(true), d.year(), d.month(), Some(d.day())),
+        };
+        match date {
+            DateRange::Year(year) => year.to_string(),
+            DateRange::Month(year, month) => match format {
+                DateFormat::Iso => date.to_string(),
+                _ => fill(pattern(false), *year, *month, None),
+            },
+            DateRange::Day(d) => day(d),
+            DateRange::Days(first, last) => format!("{} – {}", day(first), day(last)),
+        }
+    }
+}
+
+/// The names of the months for languages without their own.
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// The language of one tree of HTML pages.
+pub(in crate::output) struct Locale<'a> {
+    /// The language code, for example "de".
+    pub language: &'a str,
+    /// All configured languages, starting with the default language.
+    languages: &'a [String],
+    translations: &'a Translations,
+}
+
+/// Used in handlebars templates to describe the language of a page.
+#[derive(Serialize)]
+pub(super) struct LocaleData {
+    /// The language code for the `lang` attribute, also read by the `t` helper.
+    lang: String,
+    /// The relative path from the page to the base directory of its language, for example "../".
+    base: String,
+    /// The relative path from the language tree to the output directory with the static assets.
+    root: &'static str,
+    /// Links to the same page in all languages, empty for galleries with a single language.
+    languages: Vec<LanguageLinkData>,
+}
+
+/// Used in handlebars templates to link to a page in another language.
+#[derive(Serialize)]
+struct LanguageLinkData {
+    lang: String,
+    /// The URL of the page, relative to the current page.
+    href: String,
+    /// Whether this is the language of the current page.
+    current: bool,
+}
+
+impl<'a> Locale<'a> {
+    /// One locale per configured language, starting with the default language.
+    pub fn all(config: &'a Config, translations: &'a Translations) -> Vec<Locale<'a>> {
+        if config.languages.is_empty() {
+            return vec![Locale {
+                language: DEFAULT_LANGUAGE,
+                languages: &[],
+                translations,
+            }];
+        }
+        config
+            .languages
+            .iter()
+            .map(|language| Locale {
+                language,
+                languages: &config.languages,
+                translations,
+            })
+            .collect()
+    }
+
+    /// The locale of the default language.
+    pub fn first(config: &'a Config, translations: &'a Translations) -> Locale<'a> {
+        Locale {
+            language: config
+                .languages
+                .first()
+                .map_or(DEFAULT_LANGUAGE, String::as_str),
+            languages: &config.languages,
+            translations,
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        self.languages.first().is_none_or(|l| l == self.language)
+    }
+
+    /// The relative path from the base directory of this language to the output directory.
+    pub fn root(&self) -> &'static str {
+        if self.is_default() { "" } else { "../" }
+    }
+
+    /// The path of the output file of a page in this language.
+    ///
+    /// `url` is relative to the base directory of the language.
+    pub fn output_path(&self, config: &Config, url: impl AsRef<Path>) -> PathBuf {
+        if self.is_default() {
+            config.output_path.join(url)
+        } else {
+            config.output_path.join(self.language).join(url)
+        }
+    }
+
+    /// Describes the language of the page at `url` for the templates.
+    pub(super) fn data(&self, url: &str) -> LocaleData {
+        let to_base = "../".repeat(url.matches('/').count());
+        let languages = if self.languages.len() > 1 {
+            self.languages
+                .iter()
+                .map(|language| {
+                    let other = Locale {
+                        language,
+                        languages: self.languages,
+                        translations: self.translations,
+                    };
+                    let prefix = if other.is_default() {
+                        String::new()
+                    } else {
+                        format!("{}/", language)
+                    };
+                    LanguageLinkData {
+                        lang: language.clone(),
+                        href: format!("{}{}{}{}", to_base, self.root(), prefix, url),
+                        current: language == self.language,
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        LocaleData {
+            lang: self.language.to_owned(),
+            base: to_base,
+            root: self.root(),
+            languages,
+        }
+    }
+
+    /// Formats a date in this language, see [`Translations::format_date`].
+    pub fn format_date(&self, date: &DateRange, format: DateFormat) -> String {
+        self.translations.format_date(self.language, date, format)
+    }
+}
+
+/// The ISO 8601 form of a date for the `datetime` attribute of `<time>` elements.
+///
+/// Returns the first day and, for a range of days, the last day.
+pub(super) fn iso_date(date: &DateRange) -> (String, Option<String>) {
+    match date {
+        DateRange::Days(first, last) => (first.to_string(), Some(last.to_string())),
+        _ => (date.to_string(), None),
+    }
+}
+
+/// Parses an ISO 8601 date with the precision of a year, a month, or a day.
+fn parse_iso_date(value: &str) -> Option<DateRange> {
+    let mut parts = value.split('-');
+    let year = parts.next()?.parse().ok()?;
+    match (parts.next(), parts.next(), parts.next()) {
+        (None, _, _) => Some(DateRange::Year(year)),
+        (Some(month), None, _) => Some(DateRange::Month(
+            year,
+            Month::try_from(month.parse::<u8>().ok()?).ok()?,
+        )),
+        (Some(month), Some(day), None) => Some(DateRange::Day(
+            Date::from_calendar_date(
+                year,
+                Month::try_from(month.parse::<u8>().ok()?).ok()?,
+                day.parse().ok()?,
+            )
+            .ok()?,
+        )),
+        _ => None,
+    }
+}
+
+/// The language of the page being rendered, see [`LocaleData`].
+fn page_language(ctx: &Context) -> &str {
+    ctx.data()
+        .get("lang")
+        .and_then(|l| l.as_str())
+        .unwrap_or(DEFAULT_LANGUAGE)
+}
+
+/// The `format_date` helper formats an ISO 8601 date in the language of the page.
+///
+/// The optional second parameter is the format `numeric`, `iso`, or `long`, the default is
+/// [`Config::date_format`]. For example, `{{format_date datetime "long"}}`.
+pub(super) struct DateHelper {
+    pub format: DateFormat,
+    pub translations: Arc<Translations>,
+}
+
+impl HelperDef for DateHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let date = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("format_date", 0))?
+            .value()
+            .as_str()
+            .and_then(parse_iso_date)
+            .ok_or(RenderErrorReason::InvalidParamType("ISO 8601 date"))?;
+        let format = match h.param(1).map(|p| p.value().as_str()) {
+            None => self.format,
+            Some(Some("numeric")) => DateFormat::Numeric,
+            Some(Some("iso")) => DateFormat::Iso,
+            Some(Some("long")) => DateFormat::Long,
+            Some(_) => {
+                return Err(RenderErrorReason::InvalidParamType("numeric, iso, or long").into());
+            }
+        };
+        out.write(&html_escape(&self.translations.format_date(
+            page_language(ctx),
+            &date,
+            format,
+        )))?;
+        Ok(())
+    }
+}
+
+/// The `t` helper translates its parameter into the language of the page, see [`LocaleData`].
+pub(super) struct TranslateHelper {
+    pub translations: Arc<Translations>,
+}
+
+impl HelperDef for TranslateHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let text = h
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("t", 0))?;
+        out.write(&html_escape(
+            self.translations.translate(page_language(ctx), text),
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Locale, Translation, Translations, iso_date, parse_iso_date};
+    use crate::config::{Config, DateFormat};
+    use crate::model::DateRange;
+    use std::path::PathBuf;
+    use time::{Date, Month};
+
+    #[test]
+    fn test_single_language() {
+        let config = Config::builder("build").build();
+        let translations = Translations::default();
+        let locales = Locale::all(&config, &translations);
+        assert_eq!(locales.len(), 1);
+        assert_eq!(locales[0].language, "en-US");
+        assert_eq!(locales[0].root(), "");
+        assert_eq!(
+            locales[0].output_path(&config, "index.html"),
+            PathBuf::from("build/index.html")
+        );
+        assert!(locales[0].data("index.html").languages.is_empty());
+    }
+
+    #[test]
+    fn test_language_links() {
+        let config = Config::builder("build")
+            .languages(vec!["en".to_owned(), "de".to_owned()])
+            .build();
+        let translations = Translations::default();
+        let locales = Locale::all(&config, &translations);
+        assert_eq!(
+            locales[1].output_path(&config, "tags/birds.html"),
+            PathBuf::from("build/de/tags/birds.html")
+        );
+        let links = |locale: &Locale, url| {
+            locale
+                .data(url)
+                .languages
+                .into_iter()
+                .map(|l| (l.lang, l.href, l.current))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            links(&locales[0], "tags/birds.html"),
+            vec![
+                ("en".to_owned(), "../tags/birds.html".to_owned(), true),
+                ("de".to_owned(), "../de/tags/birds.html".to_owned(), false),
+            ]
+        );
+        assert_eq!(
+            links(&locales[1], "index.html"),
+            vec![
+                ("en".to_owned(), "../index.html".to_owned(), false),
+                ("de".to_owned(), "../de/index.html".to_owned(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_date() {
+        let config = Config::builder("build")
+            .languages(vec!["en".to_owned(), "de-AT".to_owned()])
+            .build();
+        let translations = Translations::load(&config).unwrap();
+        let locales = Locale::all(&config, &translations);
+        let (en, de) = (&locales[0], &locales[1]);
+        let first = Date::from_calendar_date(2021, Month::January, 1).unwrap();
+        let last = Date::from_calendar_date(2021, Month::January, 7).unwrap();
+        let day = DateRange::Day(first);
+        let days = DateRange::Days(first, last);
+        let month = DateRange::Month(2021, Month::March);
+        assert_eq!(en.format_date(&day, DateFormat::Numeric), "2021-01-01");
+        assert_eq!(de.format_date(&day, DateFormat::Numeric), "01.01.2021");
+        assert_eq!(de.format_date(&day, DateFormat::Iso), "2021-01-01");
+        assert_eq!(
+            de.format_date(&days, DateFormat::Numeric),
+            "01.01.2021 – 07.01.2021"
+        );
+        assert_eq!(de.format_date(&month, DateFormat::Numeric), "03.2021");
+        assert_eq!(
+            de.format_date(&DateRange::Year(2021), DateFormat::Numeric),
+            "2021"
+        );
+        assert_eq!(en.format_date(&day, DateFormat::Long), "1 January 2021");
+        assert_eq!(de.format_date(&day, DateFormat::Long), "1. Januar 2021");
+        assert_eq!(de.format_date(&month, DateFormat::Long), "März 2021");
+        assert_eq!(en.format_date(&month, DateFormat::Numeric), "2021-03");
+    }
+
+    #[test]
+    fn test_date_patterns() {
+        let mut translations = Translations::default();
+        translations.add(
+            "fr",
+            serde_json::from_str(
+                r#"{
+                    "months": ["janvier", "février", "mars", "avril", "mai", "juin", "juillet",
+                               "août", "septembre", "octobre", "novembre", "décembre"],
+                    "dates": {"numeric_day": "{d}/{m}/{yyyy}", "long_day": "{d} {month} {yyyy}"}
+                }"#,
+            )
+            .unwrap(),
+        );
+        let day = DateRange::Day(Date::from_calendar_date(2021, Month::February, 3).unwrap());
+        let month = DateRange::Month(2021, Month::August);
+        assert_eq!(
+            translations.format_date("fr-CA", &day, DateFormat::Numeric),
+            "3/2/2021"
+        );
+        assert_eq!(
+            translations.format_date("fr", &day, DateFormat::Long),
+            "3 février 2021"
+        );
+        // Missing patterns fall back to English.
+        assert_eq!(
+            translations.format_date("fr", &month, DateFormat::Numeric),
+            "2021-08"
+        );
+        assert_eq!(
+            translations.format_date("fr", &month, DateFormat::Long),
+            "août 2021"
+        );
+        assert_eq!(
+            translations.format_date("fr", &day, DateFormat::Iso),
+            "2021-02-03"
+        );
+
+        // A theme can replace single patterns.
+        translations.add(
+            "fr",
+            serde_json::from_str(r#"{"dates": {"long_month": "{month} de {yyyy}"}}"#).unwrap(),
+        );
+        assert_eq!(
+            translations.format_date("fr", &month, DateFormat::Long),
+            "août de 2021"
+        );
+        assert_eq!(
+            translations.format_date("fr", &day, DateFormat::Long),
+            "3 février 2021"
+        );
+        assert!(serde_json::from_str::<Translation>(r#"{"months": ["Jan"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_iso_date() {
+        let first = Date::from_calendar_date(2021, Month::January, 1).unwrap();
+        let last = Date::from_calendar_date(2021, Month::January, 7).unwrap();
+        assert_eq!(
+            iso_date(&DateRange::Days(first, last)),
+            ("2021-01-01".to_owned(), Some("2021-01-07".to_owned()))
+        );
+        for date in [
+            DateRange::Year(2021),
+            DateRange::Month(2021, Month::December),
+            DateRange::Day(first),
+        ] {
+            let (iso, end) = iso_date(&date);
+            assert_eq!(end, None);
+            assert_eq!(parse_iso_date(&iso), Some(date));
+        }
+        assert_eq!(parse_iso_date("2021-13"), None);
+        assert_eq!(parse_iso_date("2021-01-01-01"), None);
+        assert_eq!(parse_iso_date("today"), None);
+    }
+
+    #[test]
+    fn test_translate() {
+        let mut translations = Translations::load(&Config::builder("build").build()).unwrap();
+        assert_eq!(translations.translate("de", "Next"), "Weiter");
+        assert_eq!(translations.translate("de-CH", "photos"), "Fotos");
+        assert_eq!(translations.translate("de", "Unknown"), "Unknown");
+        assert_eq!(translations.translate("fr", "Next"), "Next");
+
+        // Added translations replace the built-in ones string by string.
+        translations.add(
+            "de",
+            serde_json::from_str(r#"{"strings": {"Next": "Vor"}}"#).unwrap(),
+        );
+        translations.add("fr", Translation::default());
+        assert_eq!(translations.translate("de", "Next"), "Vor");
+        assert_eq!(translations.translate("de", "Previous"), "Zurück");
+        assert_eq!(translations.translate("fr", "Next"), "Next");
+        assert!(serde_json::from_str::<Translation>(r#"{"string": {}}"#).is_err());
+    }
+}
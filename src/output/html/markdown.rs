@@ -10,12 +10,52 @@
 //!
 //! Some more text.
 //! ```
-use super::ImageData;
+//!
+//! An image reference can add a caption and an alt text, separated by `|`.
+//! Both are optional and plain text:
+//!
+//! ```markdown
+//! !image My image | The view from the summit | A snowy mountain top under a blue sky
+//! ```
+//!
+//! Several images can be arranged together in a grid, side by side, or on top of each other
+//! with a slider to compare them:
+//!
+//! ```markdown
+//! !gallery First image, Second image, Third image
+//!
+//! !pair Left image, Right image
+//!
+//! !compare Before, After
+//! ```
+//!
+//! Links can point to other image groups by their directory name and to images by their name,
+//! optionally prefixed with the directory name of their image group. Use angle brackets if the
+//! name contains spaces:
+//!
+//! ```markdown
+//! See also [the next day](<group:2021-01-02 Fuji, Japan>), especially [this](image:Summit)
+//! and [that](<image:2021-01-02 Fuji, Japan/Crater>).
+//! ```
+//!
+//! Links to image groups can include a heading ID or the anchor of an image on the page of the
+//! image group, like `group:2021-01-02 Fuji#day-2`.
+//!
+//! Unless disabled with [`Config::markdown_extensions`], the GitHub-flavored extensions
+//! tables, footnotes, strikethrough, and task lists are enabled, as well as custom heading IDs
+//! like `# Day 1 {#first-day}`. Headings without a custom ID get an ID from their text, so they
+//! can be linked to and listed in a table of contents.
+//!
+//! The front matter of the markdown file is not part of the output, see [`crate::input`].
+use super::{Config, HeadingData, ImageData, UnreferencedImages, has_page, url_to_string};
 
-use crate::error::PathErrorContext;
+use crate::error::{Error, PathErrorContext, path_error};
+use crate::input::split_front_matter;
+use crate::model::{Gallery, ImageGroup};
 
-use anyhow::{Result, anyhow};
-use pulldown_cmark::{Event, Parser, html};
+use anyhow::Result;
+use handlebars::html_escape;
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, TagEnd, TextMergeStream, html};
 use std::collections::{HashMap, HashSet};
 use std::{fs, path::Path};
 
@@ -25,20 +65,24 @@ pub(super) struct Rendered {
     pub html: String,
     // The images in order of appearance in the markdown file.
     pub images_seen: Vec<ImageData>,
+    // The name of the first image referenced in the markdown file.
+    pub first_image: Option<String>,
+    // The headings in order of appearance, for a table of contents.
+    pub headings: Vec<HeadingData>,
+    // The images missing from the markdown file, see [`UnreferencedImages::Warn`].
+    pub unreferenced: Vec<String>,
 }
 
-// Returns a formatted error message containing a list of image names.
-fn markdown_image_error<T>(message: &str, images: Vec<String>, input_file: &Path) -> Result<T> {
-    Err(anyhow!(
-        "{}: {}",
-        message,
-        images
-            .into_iter()
-            .map(|img| format!("\"{}\"", img))
-            .collect::<Vec<_>>()
-            .join(", ")
-    ))
-    .path_context("Error in markdown file", input_file)
+// Where a markdown file belongs, to resolve image references and links.
+pub(super) struct Context<'a> {
+    pub gallery: &'a Gallery,
+    // The image group of the markdown file, [`None`] for standalone pages and the introduction.
+    pub image_group: Option<&'a ImageGroup>,
+    // The relative path from the output file to the base directory, for example "../".
+    pub base: &'a str,
+    // The relative path from the base directory to the images, see [`super::Locale::root`].
+    pub root: &'a str,
+    pub config: &'a Config,
 }
 
 #[derive(Default)]
@@ -48,6 +92,14 @@ struct ImageStatistics {
     seen: Vec<String>,
     // Unknown images in the markdown file.
     unknown: Vec<String>,
+    // Directives with the wrong number of images.
+    invalid: Vec<String>,
+    // Links to unknown image groups or images.
+    broken_links: Vec<String>,
+    // Images of the same image group that are linked with `image:Name`.
+    linked: Vec<String>,
+    // The images with the caption and alt text of their first reference.
+    described: HashMap<String, ImageData>,
 }
 
 impl ImageStatistics {
@@ -64,38 +116,216 @@ impl ImageStatistics {
     }
 }
 
-pub(super) fn to_html(input_file: &Path, images: Vec<ImageData>) -> Result<Rendered> {
-    let input = fs::read_to_string(input_file)
-        .path_context("Failed to open image group markdown file", input_file)?;
+pub(super) fn to_html(
+    input_file: &Path,
+    images: Vec<ImageData>,
+    context: &Context,
+) -> Result<Rendered> {
+    let config = context.config;
+    let input =
+        fs::read_to_string(input_file).path_context("Failed to open markdown file", input_file)?;
 
-    let (html, stats) = {
+    let (mut html, headings, duplicate_ids, mut stats) = {
         let mut stats = ImageStatistics::default();
-        let iter = Parser::new(&input).map(|e| map_image_event(e, &images, &mut stats));
+        let (_, markdown) = split_front_matter(&input);
+        let mut events = parse(markdown, config)
+            .map(|e| {
+                let e = map_image_event(e, &images, &mut stats);
+                map_link_event(e, context, &mut stats)
+            })
+            .collect::<Vec<_>>();
+        // Heading IDs must not clash with the anchors of the images.
+        let (headings, duplicate_ids) =
+            add_heading_ids(&mut events, images.iter().map(|i| i.anchor.clone()));
         let mut out = String::new();
-        html::push_html(&mut out, iter);
-        (out, stats)
+        html::push_html(&mut out, events.into_iter());
+        (out, headings, duplicate_ids, stats)
     };
 
+    if let Some(directive) = stats.invalid.first() {
+        return Err(path_error(
+            &format!("Wrong number of images in \"{}\"", directive),
+            input_file,
+        ));
+    }
+    if !duplicate_ids.is_empty() {
+        return Err(path_error(
+            &format!("Duplicate heading IDs: {}", duplicate_ids.join(", ")),
+            input_file,
+        ));
+    }
+    let images_missing = stats.missing(&images);
+    let unreferenced_images = context
+        .image_group
+        .and_then(|g| g.unreferenced_images)
+        .unwrap_or(config.unreferenced_images);
+    if unreferenced_images == UnreferencedImages::Warn {
+        // The images left out of the page can't be linked to.
+        for target in &stats.linked {
+            if images_missing.contains(target) {
+                stats
+                    .broken_links
+                    .push(format!("image \"{}\" is not on the page", target));
+            }
+        }
+    }
+    if !stats.broken_links.is_empty() {
+        return Err(path_error(
+            &format!("Broken links: {}", stats.broken_links.join(", ")),
+            input_file,
+        ));
+    }
     if !stats.unknown.is_empty() {
-        return markdown_image_error("Unknown images in markdown file", stats.unknown, input_file);
+        return Err(Error::UnknownMarkdownImages {
+            path: input_file.to_owned(),
+            images: stats.unknown,
+        }
+        .into());
     }
 
-    // The markdown file must reference all images in the group.
-    let images_missing = stats.missing(&images);
+    let mut unreferenced = Vec::new();
     if !images_missing.is_empty() {
-        return markdown_image_error(
-            "Images present on disk but missing from the markdown file",
-            images_missing,
-            input_file,
-        );
+        match unreferenced_images {
+            UnreferencedImages::Error => {
+                return Err(Error::MissingMarkdownImages {
+                    path: input_file.to_owned(),
+                    images: images_missing,
+                }
+                .into());
+            }
+            UnreferencedImages::Warn => unreferenced = images_missing,
+            UnreferencedImages::Append => {
+                let missing = images
+                    .iter()
+                    .filter(|img| images_missing.contains(&img.name))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                html.push_str(&columns_snippet(GALLERY_COLUMNS, &missing));
+            }
+        }
     }
+    let images_seen = reorder_images(images, &stats.seen)
+        .into_iter()
+        .map(|img| stats.described.remove(&img.name).unwrap_or(img))
+        .collect();
     Ok(Rendered {
         html,
-        images_seen: reorder_images(images, &stats.seen),
+        images_seen,
+        first_image: stats.seen.first().cloned(),
+        headings,
+        unreferenced,
     })
 }
 
+// Parses markdown with the extensions enabled in the config.
+fn parse<'a>(markdown: &'a str, config: &Config) -> TextMergeStream<'a, Parser<'a>> {
+    let options = if config.markdown_extensions {
+        Options::ENABLE_TABLES
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_HEADING_ATTRIBUTES
+    } else {
+        Options::empty()
+    };
+    TextMergeStream::new(Parser::new_ext(markdown, options))
+}
+
+// Gives every heading without an ID a unique ID based on its text.
+// Returns all headings in order of appearance and the custom IDs that are not unique.
+fn add_heading_ids(
+    events: &mut [Event],
+    taken: impl Iterator<Item = String>,
+) -> (Vec<HeadingData>, Vec<String>) {
+    let mut taken = HashSet::<String>::from_iter(taken);
+    // Custom IDs come first so that generated IDs can't take them, even from earlier headings.
+    let mut duplicates = Vec::new();
+    for event in events.iter() {
+        if let Event::Start(Tag::Heading { id: Some(id), .. }) = event
+            && !taken.insert(id.to_string())
+            && !duplicates.contains(&id.to_string())
+        {
+            duplicates.push(id.to_string());
+        }
+    }
+    let mut headings = Vec::new();
+    for i in 0..events.len() {
+        let Event::Start(Tag::Heading { level, .. }) = &events[i] else {
+            continue;
+        };
+        let level = *level as usize;
+        let mut title = String::new();
+        for event in &events[i + 1..] {
+            match event {
+                Event::Text(t) | Event::Code(t) => title.push_str(t),
+                Event::End(TagEnd::Heading(_)) => break,
+                _ => {}
+            }
+        }
+        let Event::Start(Tag::Heading { id, .. }) = &mut events[i] else {
+            unreachable!("the event is a heading");
+        };
+        let anchor = match id {
+            Some(id) => id.to_string(),
+            None => {
+                let anchor = unique_id(&title, &taken);
+                *id = Some(anchor.clone().into());
+                taken.insert(anchor.clone());
+                anchor
+            }
+        };
+        headings.push(HeadingData {
+            level,
+            title,
+            anchor,
+        });
+    }
+    (headings, duplicates)
+}
+
+// Generates an ID from the text that is not in `taken`, for example `day-1-2` if `day-1` is taken.
+fn unique_id(text: &str, taken: &HashSet<String>) -> String {
+    let base = match slug::slugify(text) {
+        s if s.is_empty() => "section".to_owned(),
+        s => s,
+    };
+    (1..)
+        .map(|i| match i {
+            1 => base.clone(),
+            i => format!("{}-{}", base, i),
+        })
+        .find(|id| !taken.contains(id))
+        .expect("there are infinitely many candidates")
+}
+
+/// Extracts the plain text of a markdown file, for example for the search index.
+/// Image references are left out.
+pub(super) fn to_text(input_file: &Path, config: &Config) -> Result<String> {
+    let input =
+        fs::read_to_string(input_file).path_context("Failed to open markdown file", input_file)?;
+    let mut text = String::new();
+    for event in parse(split_front_matter(&input).1, config) {
+        match event {
+            Event::Text(t) if Directive::parse(&t).is_none() => text.push_str(&t),
+            Event::Code(t) => text.push_str(&t),
+            // Separate the text of different blocks and lines.
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::CodeBlock
+                | TagEnd::TableCell,
+            )
+            | Event::SoftBreak
+            | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    Ok(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
 // Reorder the given list of images to match their appearance in the markdown file.
+// Images that don't appear in the markdown file come last in their original order.
 fn reorder_images(images: Vec<ImageData>, images_seen: &[String]) -> Vec<ImageData> {
     // Map image names to their index.
     // If an image appears multiple times, this takes the last index.
@@ -106,11 +336,115 @@ fn reorder_images(images: Vec<ImageData>, images_seen: &[String]) -> Vec<ImageDa
             .map(|(i, img)| (img.clone(), i)),
     );
     let mut images = images.into_iter().collect::<Vec<ImageData>>();
-    images.sort_by_key(|img| sort_keys[&img.name]);
+    images.sort_by_key(|img| sort_keys.get(&img.name).copied().unwrap_or(usize::MAX));
     images
 }
 
-// Maps custom Markdown image tags to HTML snippets to include the image.
+const IMAGE_TAG_PREFIX: &str = "!image ";
+const GALLERY_TAG_PREFIX: &str = "!gallery ";
+const PAIR_TAG_PREFIX: &str = "!pair ";
+const COMPARE_TAG_PREFIX: &str = "!compare ";
+
+// The column classes of `!gallery` grids.
+const GALLERY_COLUMNS: &str = "image-gallery row-cols-2 row-cols-md-3";
+
+// A reference to an image like `!image Name | caption | alt text`.
+#[derive(Debug, PartialEq, Eq)]
+struct ImageReference<'a> {
+    name: &'a str,
+    caption: Option<&'a str>,
+    alt: Option<&'a str>,
+}
+
+impl<'a> ImageReference<'a> {
+    // Parses an image reference. Empty captions and alt texts count as missing.
+    fn parse(text: &'a str) -> Option<Self> {
+        let mut parts = text
+            .strip_prefix(IMAGE_TAG_PREFIX)?
+            .splitn(3, '|')
+            .map(str::trim);
+        let name = parts.next()?;
+        let mut optional = || parts.next().filter(|p| !p.is_empty());
+        Some(ImageReference {
+            name,
+            caption: optional(),
+            alt: optional(),
+        })
+    }
+
+    // Adds the caption and the alt text to the image.
+    fn describe(&self, img: &ImageData) -> ImageData {
+        ImageData {
+            caption: self.caption.map(str::to_owned),
+            alt: self.alt.unwrap_or(&img.alt).to_owned(),
+            ..img.clone()
+        }
+    }
+}
+
+// How a directive arranges its images.
+#[derive(Debug, PartialEq, Eq)]
+enum Arrangement {
+    // A single full-width image.
+    Single,
+    // A grid of images.
+    Gallery,
+    // Two images side by side.
+    Pair,
+    // Two images on top of each other with a slider to compare them.
+    Compare,
+}
+
+// A directive referencing one or more images, for example `!gallery A, B, C`.
+#[derive(Debug, PartialEq, Eq)]
+struct Directive<'a> {
+    arrangement: Arrangement,
+    images: Vec<ImageReference<'a>>,
+}
+
+impl<'a> Directive<'a> {
+    // Parses a directive. Directives with multiple images take a comma-separated list of names.
+    fn parse(text: &'a str) -> Option<Self> {
+        if let Some(reference) = ImageReference::parse(text) {
+            return Some(Directive {
+                arrangement: Arrangement::Single,
+                images: vec![reference],
+            });
+        }
+        let (arrangement, names) = [
+            (Arrangement::Gallery, GALLERY_TAG_PREFIX),
+            (Arrangement::Pair, PAIR_TAG_PREFIX),
+            (Arrangement::Compare, COMPARE_TAG_PREFIX),
+        ]
+        .into_iter()
+        .find_map(|(arrangement, prefix)| Some((arrangement, text.strip_prefix(prefix)?)))?;
+        let images = names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| ImageReference {
+                name,
+                caption: None,
+                alt: None,
+            })
+            .collect();
+        Some(Directive {
+            arrangement,
+            images,
+        })
+    }
+
+    // Whether the directive references the right number of images.
+    fn is_valid(&self) -> bool {
+        match self.arrangement {
+            Arrangement::Single => self.images.len() == 1,
+            Arrangement::Gallery => !self.images.is_empty(),
+            Arrangement::Pair | Arrangement::Compare => self.images.len() == 2,
+        }
+    }
+}
+
+// Maps custom Markdown image tags to HTML snippets to include the images.
 fn map_image_event<'a>(
     item: Event<'a>,
     images: &[ImageData],
@@ -120,24 +454,324 @@ fn map_image_event<'a>(
         return item;
     };
 
-    const IMAGE_TAG_PREFIX: &str = "!image ";
-    let Some(image_name) = text.strip_prefix(IMAGE_TAG_PREFIX) else {
+    let Some(directive) = Directive::parse(text) else {
+        return item;
+    };
+    if !directive.is_valid() {
+        stats.invalid.push(text.to_string());
         return item;
+    }
+    let mut found = Vec::new();
+    for reference in &directive.images {
+        let maybe_image = images.iter().find(|img| img.name == reference.name);
+        let Some(img) = maybe_image else {
+            stats.unknown.push(reference.name.to_owned());
+            continue;
+        };
+        let img = reference.describe(img);
+        stats.seen.push(img.name.clone());
+        found.push(img);
+    }
+    if found.len() != directive.images.len() {
+        return item;
+    }
+    let snippet = match directive.arrangement {
+        Arrangement::Single => image_markdown_snippet(&found[0]),
+        Arrangement::Gallery => columns_snippet(GALLERY_COLUMNS, &found),
+        Arrangement::Pair => columns_snippet("image-pair row-cols-1 row-cols-sm-2", &found),
+        Arrangement::Compare => compare_snippet(&found[0], &found[1]),
     };
-    let maybe_image = images.iter().find(|img| img.name == image_name);
-    let Some(img) = maybe_image else {
-        stats.unknown.push(image_name.to_owned());
+    for img in found {
+        stats.described.entry(img.name.clone()).or_insert(img);
+    }
+    Event::Html(snippet.into())
+}
+
+const GROUP_LINK_PREFIX: &str = "group:";
+const IMAGE_LINK_PREFIX: &str = "image:";
+
+// Resolves links to image groups and images.
+fn map_link_event<'a>(
+    item: Event<'a>,
+    context: &Context,
+    stats: &mut ImageStatistics,
+) -> Event<'a> {
+    let Event::Start(Tag::Link {
+        link_type,
+        dest_url,
+        title,
+        id,
+    }) = item
+    else {
         return item;
     };
-    stats.seen.push(image_name.to_owned());
-    Event::Html(image_markdown_snippet(img).into())
+    let dest_url = match resolve_link(&dest_url, context, stats) {
+        Some(Ok(url)) => CowStr::from(url),
+        Some(Err(e)) => {
+            stats.broken_links.push(e);
+            dest_url
+        }
+        None => dest_url,
+    };
+    Event::Start(Tag::Link {
+        link_type,
+        dest_url,
+        title,
+        id,
+    })
+}
+
+// Resolves `group:` and `image:` links to URLs.
+// Returns [`None`] for other links and a description of the problem for broken links.
+// Links to images of the same image group are recorded in `stats` because whether the image is
+// on the page is only known after the whole markdown file.
+fn resolve_link(
+    dest: &str,
+    context: &Context,
+    stats: &mut ImageStatistics,
+) -> Option<std::result::Result<String, String>> {
+    let find_group = |name: &str| {
+        context
+            .gallery
+            .image_groups
+            .iter()
+            .find(|g| g.path == Path::new(name))
+            .ok_or_else(|| format!("unknown image group \"{}\"", name))
+    };
+    let url = |url: Result<String>| url.map_err(|e| format!("{:#}", e));
+    if let Some(target) = dest.strip_prefix(GROUP_LINK_PREFIX) {
+        let (name, fragment) = match target.split_once('#') {
+            Some((name, fragment)) => (name, Some(fragment)),
+            None => (target, None),
+        };
+        return Some(find_group(name).and_then(|group| {
+            if !has_page(group, context.config) {
+                return Err(format!("image group \"{}\" has no page", name));
+            }
+            let group_url = url(group.url().and_then(|u| url_to_string(&u)))?;
+            let Some(fragment) = fragment else {
+                return Ok(format!("{}{}", context.base, group_url));
+            };
+            let anchors = page_anchors(group, context.config).map_err(|e| format!("{:#}", e))?;
+            if !anchors.contains(fragment) {
+                return Err(format!("unknown anchor \"{}\"", target));
+            }
+            Ok(format!("{}{}#{}", context.base, group_url, fragment))
+        }));
+    }
+    let target = dest.strip_prefix(IMAGE_LINK_PREFIX)?;
+    Some(match target.split_once('/') {
+        // An image in another image group links to the image on the page of the image group, or
+        // to the image itself if the image group has no page.
+        Some((group_name, image_name)) => find_group(group_name).and_then(|group| {
+            let image = group
+                .images
+                .iter()
+                .find(|i| i.name == image_name)
+                .ok_or_else(|| format!("unknown image \"{}\"", target))?;
+            if has_page(group, context.config) {
+                let anchor = slug::slugify(&image.name);
+                let anchors =
+                    page_anchors(group, context.config).map_err(|e| format!("{:#}", e))?;
+                if !anchors.contains(&anchor) {
+                    return Err(format!("image \"{}\" is not on the page", target));
+                }
+                let group_url = url(group.url().and_then(|u| url_to_string(&u)))?;
+                return Ok(format!("{}{}#{}", context.base, group_url, anchor));
+            }
+            let image_url = url(group.image_url(image).and_then(|u| url_to_string(&u)))?;
+            Ok(format!("{}{}{}", context.base, context.root, image_url))
+        }),
+        // An image in the same image group links to the image on the page.
+        None => match context.image_group {
+            Some(group) if group.images.iter().any(|i| i.name == target) => {
+                if !stats.linked.iter().any(|l| l == target) {
+                    stats.linked.push(target.to_owned());
+                }
+                Ok(format!("#{}", slug::slugify(target)))
+            }
+            Some(_) => Err(format!("unknown image \"{}\"", target)),
+            None => Err(format!(
+                "image \"{}\" without an image group, use \"image:Group/Image\"",
+                target
+            )),
+        },
+    })
+}
+
+// The anchors on the page of an image group: the images on the page and the heading IDs.
+//
+// This repeats the parts of [`to_html`] that decide about anchors, without rendering the page.
+fn page_anchors(group: &ImageGroup, config: &Config) -> Result<HashSet<String>> {
+    let image_anchors = || group.images.iter().map(|i| slug::slugify(&i.name));
+    let Some(markdown_file) = &group.markdown_file else {
+        // Pages without a markdown file show all images.
+        return Ok(image_anchors().collect());
+    };
+    let input = fs::read_to_string(markdown_file)
+        .path_context("Failed to open markdown file", markdown_file)?;
+    let mut events = parse(split_front_matter(&input).1, config).collect::<Vec<_>>();
+    let mut referenced = HashSet::new();
+    for event in &events {
+        if let Event::Text(text) = event
+            && let Some(directive) = Directive::parse(text)
+        {
+            referenced.extend(directive.images.iter().map(|r| r.name.to_owned()));
+        }
+    }
+    let (headings, _) = add_heading_ids(&mut events, image_anchors());
+    let unreferenced_images = group
+        .unreferenced_images
+        .unwrap_or(config.unreferenced_images);
+    let mut anchors = group
+        .images
+        .iter()
+        .filter(|i| unreferenced_images != UnreferencedImages::Warn || referenced.contains(&i.name))
+        .map(|i| slug::slugify(&i.name))
+        .collect::<HashSet<_>>();
+    anchors.extend(headings.into_iter().map(|h| h.anchor));
+    Ok(anchors)
 }
 
 fn image_markdown_snippet(img: &ImageData) -> String {
+    let caption = img
+        .caption
+        .as_ref()
+        .map(|c| {
+            format!(
+                r#"<figcaption class="card-body">{}</figcaption>"#,
+                html_escape(c)
+            )
+        })
+        .unwrap_or_default();
     format!(
-        r#"<div class="card shadow-sm mb-3" id="{anchor}"><a href="../{url}"><img class="card-img-top" src="../{thumbnail}"></a></div>"#,
+        r#"<figure class="card shadow-sm mb-3" id="{anchor}"><a href="../{url}"><img class="card-img-top" src="../{thumbnail}" alt="{alt}"></a>{caption}</figure>"#,
         anchor = img.anchor,
         url = img.url,
         thumbnail = img.thumbnail,
+        alt = html_escape(&img.alt),
+    )
+}
+
+// Arranges images in a row of columns that wraps according to the column classes.
+fn columns_snippet(classes: &str, images: &[ImageData]) -> String {
+    let columns = images
+        .iter()
+        .map(|img| {
+            format!(
+                r#"<div class="col"><a href="../{url}" id="{anchor}"><img class="img-fluid rounded shadow-sm" src="../{thumbnail}" alt="{alt}"></a></div>"#,
+                anchor = img.anchor,
+                url = img.url,
+                thumbnail = img.thumbnail,
+                alt = html_escape(&img.alt),
+            )
+        })
+        .collect::<String>();
+    format!(r#"<div class="row g-2 mb-3 {classes}">{columns}</div>"#)
+}
+
+// Shows the second image and reveals the first one from the left up to the slider position.
+fn compare_snippet(before: &ImageData, after: &ImageData) -> String {
+    format!(
+        r#"<div class="image-compare mb-3" id="{before_anchor}" style="--position: 50%"><img class="img-fluid" id="{after_anchor}" src="../{after_thumbnail}" alt="{after_alt}"><div class="image-compare-before"><img class="img-fluid" src="../{before_thumbnail}" alt="{before_alt}"></div><input class="image-compare-slider" type="range" min="0" max="100" value="50" aria-label="Compare images" oninput="this.parentNode.style.setProperty('--position', this.value + '%')"></div>"#,
+        before_anchor = before.anchor,
+        before_thumbnail = before.thumbnail,
+        before_alt = html_escape(&before.alt),
+        after_anchor = after.anchor,
+        after_thumbnail = after.thumbnail,
+        after_alt = html_escape(&after.alt),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Arrangement, Directive, HeadingData, ImageReference, add_heading_ids, parse};
+    use crate::config::Config;
+    use pulldown_cmark::{Event, Tag};
+
+    #[test]
+    fn test_heading_ids() {
+        let markdown = "# Day 1\n\n## Day 1\n\n## Summit\n\n## Custom {#custom}\n\n## `git` & ?!\n\n## Git {#git}\n";
+        let mut events = parse(markdown, &Config::builder("build").build()).collect::<Vec<_>>();
+        let (headings, duplicates) =
+            add_heading_ids(&mut events, ["summit".to_owned()].into_iter());
+        assert!(duplicates.is_empty());
+        let heading = |level, title: &str, anchor: &str| HeadingData {
+            level,
+            title: title.to_owned(),
+            anchor: anchor.to_owned(),
+        };
+        assert_eq!(
+            headings,
+            vec![
+                heading(1, "Day 1", "day-1"),
+                heading(2, "Day 1", "day-1-2"),
+                // The anchor of an image is taken.
+                heading(2, "Summit", "summit-2"),
+                heading(2, "Custom", "custom"),
+                // A later custom ID is taken as well.
+                heading(2, "git & ?!", "git-2"),
+                heading(2, "Git", "git"),
+            ]
+        );
+        assert!(events.iter().any(
+            |e| matches!(e, Event::Start(Tag::Heading { id: Some(id), .. }) if **id == *"day-1-2")
+        ));
+
+        // Custom IDs must not clash with each other or with the anchors of the images.
+        let markdown = "## Summit {#summit}\n\n## A {#a}\n\n## B {#a}\n\n## C {#a}\n";
+        let mut events = parse(markdown, &Config::builder("build").build()).collect::<Vec<_>>();
+        let (_, duplicates) = add_heading_ids(&mut events, ["summit".to_owned()].into_iter());
+        assert_eq!(duplicates, vec!["summit", "a"]);
+    }
+
+    #[test]
+    fn test_parse_image_reference() {
+        assert_eq!(
+            ImageReference::parse("!image Summit"),
+            Some(ImageReference {
+                name: "Summit",
+                caption: None,
+                alt: None,
+            })
+        );
+        assert_eq!(
+            ImageReference::parse("!image Summit | At the top | A snowy peak"),
+            Some(ImageReference {
+                name: "Summit",
+                caption: Some("At the top"),
+                alt: Some("A snowy peak"),
+            })
+        );
+        // Only the first two separators split, and empty parts count as missing.
+        assert_eq!(
+            ImageReference::parse("!image Summit |  | Rock | paper"),
+            Some(ImageReference {
+                name: "Summit",
+                caption: None,
+                alt: Some("Rock | paper"),
+            })
+        );
+        assert_eq!(ImageReference::parse("Summit"), None);
+    }
+
+    #[test]
+    fn test_parse_directive() {
+        let gallery = Directive::parse("!gallery A, My image ,C").unwrap();
+        assert_eq!(gallery.arrangement, Arrangement::Gallery);
+        assert_eq!(
+            gallery.images.iter().map(|i| i.name).collect::<Vec<_>>(),
+            vec!["A", "My image", "C"]
+        );
+        let pair = Directive::parse("!pair A, B").unwrap();
+        assert_eq!(pair.arrangement, Arrangement::Pair);
+        assert!(pair.is_valid());
+        assert!(!Directive::parse("!compare A").unwrap().is_valid());
+        assert!(!Directive::parse("!gallery  , ").unwrap().is_valid());
+        assert_eq!(
+            Directive::parse("!image A | B").unwrap().arrangement,
+            Arrangement::Single
+        );
+        assert_eq!(Directive::parse("!galleryA"), None);
+    }
+}
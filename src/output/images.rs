@@ -1,12 +1,16 @@
 //! Writes the images and thumbnails that make up the gallery.
-use super::Item;
-use super::{Config, RunMode, create_parent_directories};
+use super::{
+    Category, Config, Errors, Item, RunMode, create_parent_directories, has_page, large_thumbnail,
+    small_thumbnail,
+};
 
-use crate::error::PathErrorContext;
-use crate::model::{Image, ImageGroup, ThumbnailType};
+use crate::error::{Error, PathErrorContext};
+use crate::model::{Gallery, Image, ImageGroup, ThumbnailType};
 
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use base64::{Engine as _, engine::general_purpose};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::{fs, process};
 
@@ -22,6 +26,74 @@ pub(super) fn render_images(
     Ok(res)
 }
 
+/// The placeholders of the thumbnails and the dimensions of the images.
+///
+/// The pages of all languages show the same images, often on several pages each. Generating a
+/// placeholder runs imagemagick, so it happens once per thumbnail before rendering any page.
+#[derive(Default)]
+pub(super) struct Previews {
+    /// The placeholders by thumbnail path, see [`generate_placeholder`].
+    placeholders: HashMap<PathBuf, String>,
+    /// The width and height in pixels by image path.
+    dimensions: HashMap<PathBuf, (u32, u32)>,
+}
+
+impl Previews {
+    /// Generates the placeholders of all thumbnails and reads the dimensions of all images.
+    ///
+    /// Images that fail are reported to `errors` and get neither a placeholder nor dimensions.
+    pub fn new(gallery: &Gallery, config: &Config, errors: &mut Errors) -> Result<Previews> {
+        let mut images = Vec::new();
+        let mut thumbnails = Vec::new();
+        for group in &gallery.image_groups {
+            for image in &group.images {
+                images.push(image.path.clone());
+                for t in [small_thumbnail(config), large_thumbnail(config)] {
+                    if let Some(p) = thumbnail_path(group, image, config, &t)? {
+                        thumbnails.push((p, t));
+                    }
+                }
+            }
+        }
+        let mut res = Previews::default();
+        // Thumbnails are not written in dry-run mode, so there is nothing to generate the
+        // placeholders from.
+        if matches!(config.run_mode, RunMode::Normal) {
+            let placeholders = thumbnails
+                .into_par_iter()
+                .map(|(p, t)| {
+                    let placeholder = generate_placeholder(&p, &t);
+                    (p, placeholder)
+                })
+                .collect::<Vec<_>>();
+            for (path, placeholder) in placeholders {
+                if let Some(placeholder) = errors.check(Category::Images, placeholder)? {
+                    res.placeholders.insert(path, placeholder);
+                }
+            }
+        }
+        for path in images {
+            if let Some(dimensions) = errors.check(Category::Images, image_dimensions(&path))? {
+                res.dimensions.insert(path, dimensions);
+            }
+        }
+        Ok(res)
+    }
+
+    /// The placeholder of a thumbnail, empty if there is none.
+    pub fn placeholder(&self, thumbnail_path: &Path) -> String {
+        self.placeholders
+            .get(thumbnail_path)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The width and height of an image, zero if they are unknown.
+    pub fn dimensions(&self, image_path: &Path) -> (u32, u32) {
+        self.dimensions.get(image_path).copied().unwrap_or_default()
+    }
+}
+
 /// A single image ready to be written to disk.
 struct ImageFile {
     input_path: PathBuf,
@@ -46,7 +118,7 @@ fn render_image(
         input_path: image.path.clone(),
         output_path: config.output_path.join(group.image_url(image)?),
     })];
-    for t in [ThumbnailType::Small, ThumbnailType::Large] {
+    for t in [small_thumbnail(config), large_thumbnail(config)] {
         if let Some(p) = thumbnail_path(group, image, config, &t)? {
             res.push(Box::new(ThumbnailFile {
                 input_path: image.path.clone(),
@@ -68,7 +140,9 @@ fn thumbnail_path(
 ) -> Result<Option<PathBuf>> {
     match thumbnail_type {
         // No need to create a large thumbnail if the group doesn't have its own page.
-        ThumbnailType::Large if group.markdown_file.is_none() => Ok(None),
+        ThumbnailType::Large | ThumbnailType::LargeUncropped if !has_page(group, config) => {
+            Ok(None)
+        }
         _ => Ok(Some(
             config
                 .output_path
@@ -88,23 +162,24 @@ fn needs_update(input_path: &Path, output_path: &Path) -> bool {
     res().unwrap_or(true)
 }
 
-/// Generates a tiny base64-encoded placeholder image.
-pub fn generate_placeholder(thumbnail_path: &Path) -> Result<String> {
+/// Reads the width and height of an image in pixels.
+fn image_dimensions(path: &Path) -> Result<(u32, u32)> {
+    let size = imagesize::size(path).path_context("Failed to read image dimensions", path)?;
+    Ok((size.width.try_into()?, size.height.try_into()?))
+}
+
+/// Generates a tiny base64-encoded placeholder image with the aspect ratio of the thumbnail.
+fn generate_placeholder(thumbnail_path: &Path, thumbnail_type: &ThumbnailType) -> Result<String> {
+    let crop: &[&str] = if thumbnail_type.is_cropped() {
+        &["-gravity", "center", "-crop", "30x20+0+0", "+repage"]
+    } else {
+        &[]
+    };
     let output = process::Command::new("convert")
         .arg(thumbnail_path)
-        .args([
-            "-resize",
-            "30x",
-            "-gravity",
-            "center",
-            "-crop",
-            "30x20+0+0",
-            "+repage",
-            "-strip",
-            "-define",
-            "webp:method=6",
-            "webp:-",
-        ])
+        .args(["-resize", "30x"])
+        .args(crop)
+        .args(["-strip", "-define", "webp:method=6", "webp:-"])
         .output()
         .path_context(
             "Failed to run imagemagick 'convert' for placeholder",
@@ -112,12 +187,11 @@ pub fn generate_placeholder(thumbnail_path: &Path) -> Result<String> {
         )?;
 
     if !output.status.success() {
-        return Err(anyhow!(
-            "Failed to create placeholder: \"{}\"\nstderr:\n{}\n\nstdout:\n{}\n",
-            thumbnail_path.to_string_lossy(),
-            String::from_utf8_lossy(&output.stderr),
-            String::from_utf8_lossy(&output.stdout),
-        ));
+        return Err(Error::ImageConversion {
+            path: thumbnail_path.to_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+        .into());
     }
 
     let base64 = general_purpose::STANDARD.encode(&output.stdout);
@@ -131,14 +205,14 @@ impl Item for ImageFile {
         }
         match &config.run_mode {
             RunMode::Normal => {
+                // Reports an unreadable input image with its own path, not as a write error.
+                fs::File::open(&self.input_path)
+                    .path_context("Failed to read image", &self.input_path)?;
                 create_parent_directories(&self.output_path)?;
-                fs::copy(&self.input_path, &self.output_path).path_context(
-                    &format!(
-                        "Failed to copy image to \"{}\"",
-                        self.output_path.to_string_lossy()
-                    ),
-                    &self.input_path,
-                )?;
+                fs::copy(&self.input_path, &self.output_path).map_err(|source| Error::Write {
+                    path: self.output_path.clone(),
+                    source,
+                })?;
             }
             RunMode::DryRun => {
                 println!("Image: \"{}\"", self.output_path.to_string_lossy());
@@ -146,6 +220,10 @@ impl Item for ImageFile {
         }
         Ok(())
     }
+
+    fn category(&self) -> Category {
+        Category::Images
+    }
 }
 
 impl Item for ThumbnailFile {
@@ -155,34 +233,62 @@ impl Item for ThumbnailFile {
         }
         match &config.run_mode {
             RunMode::Normal => match self.thumbnail_type {
-                ThumbnailType::Small => self.write_internal("400x", "400x267+0+0"),
-                ThumbnailType::Large => self.write_internal("2000x", "2000x1335+0+0"),
+                ThumbnailType::Small => self.write_internal("400x", Some("400x267+0+0")),
+                ThumbnailType::Large => self.write_internal("2000x", Some("2000x1335+0+0")),
+                ThumbnailType::SmallUncropped => self.write_internal("400x", None),
+                ThumbnailType::LargeUncropped => self.write_internal("2000x", None),
             },
             RunMode::DryRun => Ok(()), // Thumbnails are silent in dry-run mode.
         }
     }
+
+    fn category(&self) -> Category {
+        Category::Thumbnails
+    }
 }
 
 impl ThumbnailFile {
-    fn write_internal(&self, dimensions: &str, crop: &str) -> Result<()> {
+    fn write_internal(&self, dimensions: &str, crop: Option<&str>) -> Result<()> {
         super::create_parent_directories(&self.output_path)?;
-        let result = process::Command::new("convert")
-            .arg(&self.input_path)
-            .args([
-                "-resize", dimensions, "-gravity", "center", "-crop", crop, "+repage", "-quality",
-                "80",
-            ])
+        let mut command = process::Command::new("convert");
+        command.arg(&self.input_path).args(["-resize", dimensions]);
+        if let Some(crop) = crop {
+            command.args(["-gravity", "center", "-crop", crop, "+repage"]);
+        }
+        let result = command
+            .args(["-quality", "80"])
             .arg(&self.output_path)
             .output()
             .path_context("Failed to run imagemagick 'convert'", &self.input_path)?;
         if !result.status.success() {
-            return Err(anyhow!(
-                "Failed to create thumbnail: \"{}\"\nstderr:\n{}\n\nstdout:\n{}\n",
-                self.input_path.to_string_lossy(),
-                String::from_utf8_lossy(&result.stderr),
-                String::from_utf8_lossy(&result.stdout),
-            ));
+            return Err(Error::ImageConversion {
+                path: self.input_path.clone(),
+                stderr: String::from_utf8_lossy(&result.stderr).into_owned(),
+            }
+            .into());
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ImageFile;
+    use crate::config::Config;
+    use crate::error::error_path;
+    use crate::output::Item;
+
+    #[test]
+    fn test_missing_input_image() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let image = ImageFile {
+            input_path: tempdir.path().join("missing.webp"),
+            output_path: tempdir.path().join("img/missing.webp"),
+        };
+        let err = image
+            .write(&Config::builder(tempdir.path()).build())
+            .unwrap_err();
+        assert_eq!(error_path(&err), Some(image.input_path.as_path()));
+        assert!(!tempdir.path().join("img").exists());
+    }
+}
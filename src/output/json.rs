@@ -0,0 +1,63 @@
+//! Writes the gallery model as JSON for consumption by other tools.
+//!
+//! The JSON file contains the same data that the HTML templates get.
+use super::images::Previews;
+use super::{Category, Config, Errors, Item, RunMode, create_parent_directories, html};
+
+use crate::error::Error;
+use crate::model::Gallery;
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+/// Renders the whole gallery into an [`Item`] for the file `gallery.json`.
+///
+/// Image groups that fail to render are left out if `errors` collects errors.
+pub(super) fn render_gallery_json(
+    gallery: &Gallery,
+    config: &Config,
+    translations: &html::Translations,
+    previews: &Previews,
+    errors: &mut Errors,
+) -> Result<Box<dyn Item + Send>> {
+    // There are no language trees for JSON output, only the default language.
+    let locale = html::Locale::first(config, translations);
+    let data = html::gallery_data(gallery, config, &locale, previews, errors)?;
+    Ok(Box::new(JSONFile {
+        content: serde_json::to_string_pretty(&data)
+            .with_context(|| "Failed to serialize gallery to JSON")?,
+        output_path: config.output_path.join("gallery.json"),
+    }))
+}
+
+/// A JSON file ready to be written to disk.
+pub(super) struct JSONFile {
+    pub(super) content: String,
+    pub(super) output_path: PathBuf,
+}
+
+impl Item for JSONFile {
+    fn write(&self, config: &Config) -> Result<()> {
+        match &config.run_mode {
+            RunMode::Normal => {
+                create_parent_directories(&self.output_path)?;
+                fs::write(&self.output_path, &self.content).map_err(|source| {
+                    Error::Write {
+                        path: self.output_path.clone(),
+                        source,
+                    }
+                    .into()
+                })
+            }
+            RunMode::DryRun => {
+                println!("JSON:  \"{}\"", self.output_path.to_string_lossy());
+                Ok(())
+            }
+        }
+    }
+
+    fn category(&self) -> Category {
+        Category::Pages
+    }
+}
@@ -0,0 +1,136 @@
+// The lightbox of the overview and the image group pages.
+// The images come from the JSON in the element with the ID "lightbox-images".
+function initLightbox(images) {
+  openLightbox = () => { };
+  // The lightbox doesn't work well on small devices.
+  if (document.documentElement.clientWidth < 700) {
+    return;
+  }
+
+  let zoomscript = null;
+  const lightbox = basicLightbox.create(`
+<div id="lightboxoutercontainer">
+<div id="lightboxclose">❌</div>
+<div id="lightboxtitle"></div>
+<div id="lightboximagecontainer" onclick="closeLightbox()">
+  <img id="lightboximage" onclick="event.stopPropagation()">
+</div>
+<div class="lightboxarrow" id="lightboxarrowright" onclick="openNextImage()">►</div>
+<div class="lightboxarrow" id="lightboxarrowleft" onclick="openPreviousImage()">◄</div>
+</div>
+`, {
+    onShow: (lightbox) => {
+      lightbox.element().querySelector('#lightboxclose').onclick = lightbox.close
+      if (zoomscript) {
+        zoomscript.destroy();
+      }
+      zoomscript = WZoom.create(lightbox.element().querySelector('#lightboximage'), {
+        smoothTime: 0.1,
+        smoothTimeDrag: 0,
+        maxScale: 1.5,
+        speed: 1.3,
+        zoomOnClick: false,
+      });
+    },
+    onClose: () => history.replaceState(null, '', '#'),
+  })
+
+  let currentImageIndex = 0;
+  let nextImagePreloaded = -1;
+  let preloadedImg = new Image();
+  const imageContainer = lightbox.element().querySelector('#lightboximage');
+  // Disable dragging, it interferes with the panning from the zoom script.
+  imageContainer.ondragstart = () => { return false; };
+  imageContainer.onload = () => {
+    zoomscript.prepare();
+    imageContainer.style.filter = '';
+    imageContainer.style.visibility = 'visible';
+    // Preload next image
+    if (currentImageIndex < images.length - 1) {
+      preloadedImg.src = '';
+      preloadedImg.onload = () => { nextImagePreloaded = currentImageIndex + 1; }
+      preloadedImg.src = images[currentImageIndex + 1].url;
+    }
+  }
+  window.onresize = () => { zoomscript.prepare(); };
+
+  const newImg = new Image();
+  newImg.onload = () => {
+    imageContainer.style.visibility = 'hidden';  // Minimize flickering
+    imageContainer.src = newImg.src;
+  }
+  function updateLightboxImage() {
+    // Update the title immediately for responsiveness.
+    lightbox.element().querySelector('#lightboxtitle').textContent = images[currentImageIndex].title;
+
+    // Load the image while keeping the old image visible to minimize flickering from the zoom script.
+    const newURL = images[currentImageIndex].url;
+    newImg.src = images[currentImageIndex].url;
+    history.replaceState(null, '', '#' + newURL);
+    if (nextImagePreloaded !== currentImageIndex) { // not preloaded, loading could be slow
+      imageContainer.style.filter = 'brightness(30%)';
+    }
+    // Cancel pending preload.
+    preloadedImg.onload = () => {};
+    preloadedImg.src = '';
+  }
+
+  openPreviousImage = () => {
+    if (currentImageIndex > 0) {
+      --currentImageIndex
+    };
+    updateLightboxImage();
+  }
+  openNextImage = () => {
+    if (currentImageIndex < images.length - 1) {
+      ++currentImageIndex;
+    }
+    updateLightboxImage();
+  }
+
+  document.addEventListener(
+    "keydown",
+    (event) => {
+      switch (event.code) {
+        case "ArrowLeft": openPreviousImage(); break;
+        case "ArrowRight": openNextImage(); break;
+        case "Escape": closeLightbox(); break;
+      }
+    },
+    false,
+  );
+
+  openLightbox = (url) => {
+    const i = images.findIndex((img) => img.url === url)
+    if (i == -1) {
+      return true;
+    }
+    currentImageIndex = i;
+    updateLightboxImage();
+    lightbox.show();
+    return false;
+  }
+
+  closeLightbox = () => lightbox.close();
+
+  // Opens the lightbox for links to its images, except when the link opens in a new tab.
+  document.addEventListener("click", (event) => {
+    const link = event.target.closest("a[href]");
+    if (!link || event.button !== 0 || event.ctrlKey || event.metaKey || event.shiftKey || event.altKey) {
+      return;
+    }
+    if (openLightbox(link.getAttribute("href")) === false) {
+      event.preventDefault();
+    }
+  });
+
+  const anchor = window.location.hash.substring(1);
+  if (anchor) {
+    openLightbox(anchor);
+  }
+}
+
+const lightboxImages = document.getElementById("lightbox-images");
+if (lightboxImages) {
+  initLightbox(JSON.parse(lightboxImages.textContent));
+}
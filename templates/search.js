@@ -0,0 +1,36 @@
+document.addEventListener("DOMContentLoaded", function () {
+  const input = document.getElementById("search");
+  const index = document.getElementById("search-index");
+  if (!input || !index) {
+    return;
+  }
+
+  // Lowercase and strip accents so that "zurich" finds "Zürich".
+  function normalize(text) {
+    return text.normalize("NFD").replace(/[\u0300-\u036f]/g, "").toLowerCase();
+  }
+
+  // Maps image group URLs to the searchable text of the group.
+  const groups = new Map(
+    JSON.parse(index.textContent).map((group) => [
+      group.url,
+      normalize([group.title, group.date, group.text, ...group.tags].join(" ")),
+    ])
+  );
+
+  function filter() {
+    const terms = normalize(input.value).split(/\s+/).filter((term) => term);
+    document.querySelectorAll("[data-search-group]").forEach((card) => {
+      const text =
+        (groups.get(card.dataset.searchGroup) || "") +
+        " " +
+        normalize(card.dataset.searchImage);
+      card.hidden = !terms.every((term) => text.includes(term));
+    });
+  }
+
+  input.addEventListener("input", filter);
+  input.disabled = false;
+  // The browser may restore a search from the last visit.
+  filter();
+});
@@ -2,7 +2,11 @@
 //!
 //! The tests here require imagemagick (the `convert` binary) to be in the `PATH`
 //! or the tests will fail when trying to generate thumbnails.
-use std::{fs, path::Path, process::Command};
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
 
 /// A valid 1-pixel sized webp image.
 /// The image needs to be valid to test the generation of thumbnails.
@@ -15,7 +19,24 @@ fn run_main(
     footer: &str,
     extra_args: &[&str],
 ) {
-    let output = Command::new(env!("CARGO_BIN_EXE_gallery"))
+    let output = run_main_output(inputdir, outputdir, page_title, footer, extra_args);
+    if !output.status.success() {
+        panic!(
+            "Error:\nstderr:\n{}\n\nstdout:\n{}\n",
+            String::from_utf8_lossy(&output.stderr),
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+}
+
+fn run_main_output(
+    inputdir: &Path,
+    outputdir: &Path,
+    page_title: &str,
+    footer: &str,
+    extra_args: &[&str],
+) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_gallery"))
         .args([
             &("--page_title=".to_owned() + page_title),
             &("--footer=".to_owned() + footer),
@@ -24,14 +45,7 @@ fn run_main(
         ])
         .args(extra_args)
         .output()
-        .expect("Failed to run main");
-    if !output.status.success() {
-        panic!(
-            "Error:\nstderr:\n{}\n\nstdout:\n{}\n",
-            String::from_utf8_lossy(&output.stderr),
-            String::from_utf8_lossy(&output.stdout)
-        );
-    }
+        .expect("Failed to run main")
 }
 
 #[test]
@@ -113,3 +127,1041 @@ fn test_dry_run_mode() {
         "Created output directory in dry-run mode."
     );
 }
+
+#[test]
+fn test_keep_going_reports_all_errors() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    fs::create_dir(inputdir).unwrap();
+    for (group, image) in [
+        ("2021-01-01 Fuji, Japan", "Summit"),
+        ("2022-01-01 Alps", "Pass"),
+    ] {
+        fs::create_dir(inputdir.join(group)).unwrap();
+        fs::write(
+            inputdir.join(group).join(image.to_owned() + ".webp"),
+            DUMMY_WEBP,
+        )
+        .unwrap();
+        fs::write(inputdir.join(group).join("index.md"), "!image Unknown\n").unwrap();
+    }
+
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &["--keep_going"]);
+    assert!(!output.status.success());
+    assert_eq!(output.status.code(), Some(4));
+    let report = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        report.contains("2021-01-01 Fuji, Japan/index.md"),
+        "{}",
+        report
+    );
+    assert!(report.contains("2022-01-01 Alps/index.md"), "{}", report);
+    // The images are still written.
+    assert!(
+        outputdir
+            .join("img/2021-01-01-fuji-japan-summit.webp")
+            .is_file()
+    );
+    assert!(outputdir.join("img/2022-01-01-alps-pass.webp").is_file());
+}
+
+#[test]
+fn test_missing_input_exit_code() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &[]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.starts_with("Error: Input directory not found"),
+        "{}",
+        stderr
+    );
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_json_message_format() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    fs::create_dir(inputdir).unwrap();
+    fs::write(inputdir.join("notes.txt"), "").unwrap();
+    fs::create_dir(inputdir.join("2021-01-01 Fuji, Japan")).unwrap();
+    fs::write(
+        inputdir.join("2021-01-01 Fuji, Japan/index.md"),
+        "!image Unknown\n",
+    )
+    .unwrap();
+
+    let output = run_main_output(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--message_format=json"],
+    );
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let messages = stderr
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 2, "{}", stderr);
+    assert_eq!(messages[0]["level"], "warning");
+    assert_eq!(
+        messages[0]["path"],
+        inputdir.join("notes.txt").to_str().unwrap()
+    );
+    assert_eq!(messages[1]["level"], "error");
+    assert_eq!(
+        messages[1]["path"],
+        inputdir
+            .join("2021-01-01 Fuji, Japan/index.md")
+            .to_str()
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_json_format() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    fs::create_dir(inputdir).unwrap();
+    fs::create_dir(inputdir.join("2021-01-01 Fuji, Japan")).unwrap();
+    fs::write(
+        inputdir.join("2021-01-01 Fuji, Japan/Summit.webp"),
+        DUMMY_WEBP,
+    )
+    .unwrap();
+    fs::write(
+        inputdir.join("2021-01-01 Fuji, Japan/index.md"),
+        "Climbing.\n\n!image Summit\n",
+    )
+    .unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &["--format=json"]);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(outputdir.join("gallery.json")).unwrap()).unwrap();
+    assert_eq!(json["title"], "Title");
+    let group = &json["image_groups"][0];
+    assert_eq!(group["title"], "Fuji, Japan");
+    assert_eq!(group["date"], "2021-01-01");
+    assert!(
+        group["markdown_content"]
+            .as_str()
+            .unwrap()
+            .contains("Climbing.")
+    );
+    let image = &group["images"][0];
+    assert_eq!(image["name"], "Summit");
+    assert_eq!(image["url"], "img/2021-01-01-fuji-japan-summit.webp");
+    assert_eq!(
+        image["thumbnail"],
+        "thumbnails/small/2021-01-01-fuji-japan-summit.webp"
+    );
+    assert_eq!(image["width"], 1);
+    assert_eq!(image["height"], 1);
+
+    // Images are written, but no HTML pages.
+    assert!(
+        outputdir
+            .join("img/2021-01-01-fuji-japan-summit.webp")
+            .is_file()
+    );
+    assert!(!outputdir.join("index.html").exists());
+    assert!(!outputdir.join("html").exists());
+}
+
+#[test]
+fn test_search_index() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    fs::create_dir(inputdir).unwrap();
+    fs::create_dir(inputdir.join("2021-01-01 Fuji, Japan")).unwrap();
+    fs::write(
+        inputdir.join("2021-01-01 Fuji, Japan/Summit.webp"),
+        DUMMY_WEBP,
+    )
+    .unwrap();
+    fs::write(
+        inputdir.join("2021-01-01 Fuji, Japan/index.md"),
+        "We reached the *crater*.\n\n!image Summit\n",
+    )
+    .unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &[]);
+
+    // The index is embedded into the page, so the search works without a server.
+    let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    let index = embedded_json(&overview, "search-index");
+    let group = &index[0];
+    assert_eq!(group["url"], "html/2021-01-01-fuji-japan.html");
+    assert_eq!(group["title"], "Fuji, Japan");
+    assert_eq!(group["date"], "2021-01-01");
+    assert_eq!(group["text"], "We reached the crater.");
+    assert!(!outputdir.join("search-index.json").exists());
+    assert!(overview.contains(
+        r#"data-search-group="html/2021-01-01-fuji-japan.html" data-search-image="Summit""#
+    ));
+}
+
+#[test]
+fn test_tags() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    fs::create_dir(inputdir).unwrap();
+    fs::create_dir(inputdir.join("2021-01-01 Fuji, Japan")).unwrap();
+    fs::write(
+        inputdir.join("2021-01-01 Fuji, Japan/Summit.webp"),
+        DUMMY_WEBP,
+    )
+    .unwrap();
+    fs::write(
+        inputdir.join("2021-01-01 Fuji, Japan/index.md"),
+        "---\ntags: Hiking, Japan\n---\nWe reached the crater.\n\n!image Summit\n",
+    )
+    .unwrap();
+    fs::create_dir(inputdir.join("2021-02-01 Zoo")).unwrap();
+    fs::write(inputdir.join("2021-02-01 Zoo/Parrot.webp"), DUMMY_WEBP).unwrap();
+    fs::write(inputdir.join("2021-02-01 Zoo/Lion.webp"), DUMMY_WEBP).unwrap();
+    fs::write(
+        inputdir.join("2021-02-01 Zoo/Parrot.webp.xmp"),
+        r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description>
+<dc:subject><rdf:Bag><rdf:li>Birds</rdf:li></rdf:Bag></dc:subject>
+</rdf:Description></rdf:RDF></x:xmpmeta>"#,
+    )
+    .unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &["--strict"]);
+
+    let hiking = fs::read_to_string(outputdir.join("tags/hiking.html")).unwrap();
+    assert!(hiking.contains("2021-01-01-fuji-japan-summit.webp"));
+    let birds = fs::read_to_string(outputdir.join("tags/birds.html")).unwrap();
+    assert!(birds.contains("2021-02-01-zoo-parrot.webp"));
+    assert!(!birds.contains("2021-02-01-zoo-lion.webp"));
+
+    let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    for tag in ["birds", "hiking", "japan"] {
+        assert!(overview.contains(&format!(r#"href="tags/{}.html""#, tag)));
+    }
+
+    // The front matter is not part of the image group page.
+    let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji-japan.html")).unwrap();
+    assert!(!group.contains("tags:"));
+    assert!(group.contains(r#"href="../tags/hiking.html""#));
+}
+
+#[test]
+fn test_pagination_and_archives() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    fs::create_dir(inputdir).unwrap();
+    for group in ["2020-05-01 Spring", "2021-01-01 Fuji", "2021-02-01 Zoo"] {
+        fs::create_dir(inputdir.join(group)).unwrap();
+        fs::write(inputdir.join(group).join("Image.webp"), DUMMY_WEBP).unwrap();
+    }
+
+    run_main(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--page_size=2", "--month_archives"],
+    );
+
+    // Most recent first: Zoo and Fuji on the first page, Spring on the second.
+    let first = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    assert!(first.contains("2021-02-01-zoo-image.webp"));
+    assert!(first.contains("2021-01-01-fuji-image.webp"));
+    assert!(!first.contains("2020-05-01-spring-image.webp"));
+    assert!(first.contains(r#"href="index-page-2.html""#));
+    let second = fs::read_to_string(outputdir.join("index-page-2.html")).unwrap();
+    assert!(second.contains("2020-05-01-spring-image.webp"));
+    assert!(!outputdir.join("index-page-3.html").exists());
+
+    // The year navigation follows the gallery order.
+    let year_2021 = first.find(r#"href="2021.html""#).unwrap();
+    let year_2020 = first.find(r#"href="2020.html""#).unwrap();
+    assert!(year_2021 < year_2020);
+
+    let archive = fs::read_to_string(outputdir.join("2021.html")).unwrap();
+    assert!(archive.contains("2021-01-01-fuji-image.webp"));
+    assert!(!archive.contains("2020-05-01-spring-image.webp"));
+    assert!(archive.contains(r#"href="2021-02.html""#));
+    assert!(!archive.contains(r#"href="2020-05.html""#));
+    let month = fs::read_to_string(outputdir.join("2021-02.html")).unwrap();
+    assert!(month.contains("2021-02-01-zoo-image.webp"));
+    assert!(!month.contains("2021-01-01-fuji-image.webp"));
+}
+
+#[test]
+fn test_covers_overview() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    fs::create_dir(inputdir).unwrap();
+    // The cover comes from the front matter.
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir(&fuji).unwrap();
+    for image in ["Summit", "Valley"] {
+        fs::write(fuji.join(image.to_owned() + ".webp"), DUMMY_WEBP).unwrap();
+    }
+    fs::write(
+        fuji.join("index.md"),
+        "---\ncover: Valley\n---\n!image Summit\n\n!image Valley\n",
+    )
+    .unwrap();
+    // The cover is the first image referenced in the markdown file.
+    let zoo = inputdir.join("2021-02-01 Zoo");
+    fs::create_dir(&zoo).unwrap();
+    for image in ["Lion", "Parrot"] {
+        fs::write(zoo.join(image.to_owned() + ".webp"), DUMMY_WEBP).unwrap();
+    }
+    fs::write(zoo.join("index.md"), "!image Parrot\n\n!image Lion\n").unwrap();
+    // The cover is the first image, and the group gets a page without a markdown file.
+    let spring = inputdir.join("2021-03-01 Spring");
+    fs::create_dir(&spring).unwrap();
+    for image in ["Blossom", "Tree", "Meadow"] {
+        fs::write(spring.join(image.to_owned() + ".webp"), DUMMY_WEBP).unwrap();
+    }
+
+    run_main(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--overview=covers"],
+    );
+
+    let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    assert!(overview.contains("thumbnails/small/2021-01-01-fuji-valley.webp"));
+    assert!(!overview.contains("thumbnails/small/2021-01-01-fuji-summit.webp"));
+    assert!(overview.contains("thumbnails/small/2021-02-01-zoo-parrot.webp"));
+    assert!(!overview.contains("thumbnails/small/2021-02-01-zoo-lion.webp"));
+    assert!(overview.contains("thumbnails/small/2021-03-01-spring-blossom.webp"));
+    assert!(overview.contains("3 photos"));
+    assert!(overview.contains(r#"href="html/2021-03-01-spring.html""#));
+
+    let spring_page = fs::read_to_string(outputdir.join("html/2021-03-01-spring.html")).unwrap();
+    assert!(spring_page.contains("thumbnails/large/2021-03-01-spring-meadow.webp"));
+}
+
+#[test]
+fn test_unknown_cover() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    fs::write(fuji.join("Summit.webp"), DUMMY_WEBP).unwrap();
+    fs::write(
+        fuji.join("index.md"),
+        "---\ncover: Valley\n---\n!image Summit\n",
+    )
+    .unwrap();
+
+    let output = run_main_output(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--overview=covers"],
+    );
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unknown cover image \"Valley\" in front matter"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_layouts() {
+    for (layout, class) in [
+        ("justified", "justified-row"),
+        ("masonry", "masonry-column"),
+    ] {
+        let tempdir_raw = tempfile::tempdir().unwrap();
+        let tempdir = tempdir_raw.path();
+        let inputdir = &tempdir.join("input");
+        let outputdir = &tempdir.join("output");
+
+        let fuji = inputdir.join("2021-01-01 Fuji");
+        fs::create_dir_all(&fuji).unwrap();
+        for image in ["Summit", "Valley"] {
+            fs::write(fuji.join(image.to_owned() + ".webp"), DUMMY_WEBP).unwrap();
+        }
+
+        run_main(
+            inputdir,
+            outputdir,
+            "Title",
+            "Footer",
+            &["--overview=covers", &format!("--layout={}", layout)],
+        );
+
+        // The cover cards on the overview and the image group page use uncropped thumbnails.
+        let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+        assert!(overview.contains("thumbnails/small-uncropped/2021-01-01-fuji-summit.webp"));
+        let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+        assert!(group.contains(class), "{}", layout);
+        assert!(group.contains("thumbnails/large-uncropped/2021-01-01-fuji-valley.webp"));
+        assert!(group.contains("aspect-ratio: 1 / 1"));
+        assert!(!outputdir.join("thumbnails/small").exists());
+
+        run_main(
+            inputdir,
+            outputdir,
+            "Title",
+            "Footer",
+            &[&format!("--layout={}", layout)],
+        );
+        let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+        assert!(overview.contains(class), "{}", layout);
+    }
+}
+
+#[test]
+fn test_image_group_navigation() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    for group in ["2020-05-01 Spring", "2021-01-01 Fuji", "2021-02-01 Zoo"] {
+        fs::create_dir_all(inputdir.join(group)).unwrap();
+        fs::write(inputdir.join(group).join("Image.webp"), DUMMY_WEBP).unwrap();
+        fs::write(inputdir.join(group).join("index.md"), "!image Image\n").unwrap();
+    }
+
+    run_main(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--oldest_first", "--page_size=2"],
+    );
+
+    let fuji = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    assert!(fuji.contains(r#"<a href="../html/2020-05-01-spring.html" rel="prev">← Spring</a>"#));
+    assert!(fuji.contains(r#"<a href="../html/2021-02-01-zoo.html" rel="next">Zoo →</a>"#));
+    assert!(fuji.contains(r#"<a href="../index.html">Title</a>"#));
+    assert!(fuji.contains(r#"<a href="../2021.html">2021</a>"#));
+
+    // The last group is on the second overview page and has no next group.
+    let zoo = fs::read_to_string(outputdir.join("html/2021-02-01-zoo.html")).unwrap();
+    assert!(zoo.contains(r#"<a href="../index-page-2.html">Title</a>"#));
+    assert!(!zoo.contains(r#"rel="next""#));
+}
+
+#[test]
+fn test_pages() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    fs::write(fuji.join("Summit.webp"), DUMMY_WEBP).unwrap();
+    fs::create_dir_all(inputdir.join("pages")).unwrap();
+    fs::write(inputdir.join("pages/About me.md"), "Hello *world*\n").unwrap();
+    fs::write(inputdir.join("pages/Contact.md"), "Write a letter.\n").unwrap();
+    fs::write(inputdir.join("index.md"), "Welcome to my **photos**.\n").unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &["--page_size=1"]);
+
+    let about = fs::read_to_string(outputdir.join("pages/about-me.html")).unwrap();
+    assert!(about.contains("<p>Hello <em>world</em></p>"));
+    assert!(about.contains(r#"<a href="../pages/contact.html">Contact</a>"#));
+    assert!(about.contains(
+        r#"<a href="../pages/about-me.html" class="fw-bold" aria-current="page">About me</a>"#
+    ));
+
+    // The menu is on every page, the introduction only at the top of the overview.
+    let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    assert!(overview.contains(r#"<a href="pages/about-me.html">About me</a>"#));
+    assert!(overview.contains("<p>Welcome to my <strong>photos</strong>.</p>"));
+    let archive = fs::read_to_string(outputdir.join("2021.html")).unwrap();
+    assert!(archive.contains(r#"<a href="pages/contact.html">Contact</a>"#));
+    assert!(!archive.contains("Welcome"));
+
+    // Without languages, dotted names are not translations.
+    fs::write(inputdir.join("pages/Notes.old.md"), "Old notes\n").unwrap();
+    run_main(inputdir, outputdir, "Title", "Footer", &["--strict"]);
+    let notes = fs::read_to_string(outputdir.join("pages/notes-old.html")).unwrap();
+    assert!(notes.contains("<p>Old notes</p>"));
+    assert!(notes.contains(r#"aria-current="page">Notes.old</a>"#));
+    fs::remove_file(inputdir.join("pages/Notes.old.md")).unwrap();
+
+    // Pages don't belong to an image group and can't reference images.
+    fs::write(inputdir.join("pages/Contact.md"), "!image Summit\n").unwrap();
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Summit"), "{}", stderr);
+}
+
+#[test]
+fn test_image_captions() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    for image in ["Summit", "Valley"] {
+        fs::write(fuji.join(image.to_owned() + ".webp"), DUMMY_WEBP).unwrap();
+    }
+    fs::write(
+        fuji.join("index.md"),
+        "!image Summit | Snow & ice | A \"white\" peak\n\n!image Valley\n",
+    )
+    .unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &[]);
+
+    let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    assert!(group.contains(r#"alt="A &quot;white&quot; peak"></a><figcaption class="card-body">Snow &amp; ice</figcaption></figure>"#));
+    // Without an explicit alt text, the alt text is the image name.
+    assert!(group.contains(r#"alt="Valley"></a></figure>"#));
+
+    // The overview cards and the lightbox show the caption instead of the image name.
+    let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    assert!(overview.contains(r#"<small class="text-muted">Snow &amp; ice</small>"#));
+    assert!(overview.contains(r#"<small class="text-muted">Valley</small>"#));
+    assert!(overview.contains(r#""title":"2021-01-01 Fuji, Snow \u0026 ice","#));
+}
+
+#[test]
+fn test_markdown_extensions() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    fs::write(fuji.join("Summit.webp"), DUMMY_WEBP).unwrap();
+    fs::write(
+        fuji.join("index.md"),
+        "# Day 1\n\n| Peak | Height |\n| --- | --- |\n| Fuji | 3776 m |\n\n~~Rain~~\n\n\
+         ## Summit\n\n!image Summit\n",
+    )
+    .unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &[]);
+    let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    assert!(group.contains("<td>Fuji</td>"));
+    assert!(group.contains("<del>Rain</del>"));
+    // The heading ID doesn't clash with the anchor of the image.
+    assert!(group.contains(r#"<h2 id="summit-2">Summit</h2>"#));
+    assert!(group.contains(r##"<li class="toc-level-1"><a href="#day-1">Day 1</a></li>"##));
+
+    run_main(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--plain_markdown"],
+    );
+    let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    assert!(!group.contains("<td>"));
+    assert!(group.contains(r#"<h1 id="day-1">Day 1</h1>"#));
+}
+
+#[test]
+fn test_layout_directives() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    for image in ["A", "B", "C", "D", "E"] {
+        fs::write(fuji.join(image.to_owned() + ".webp"), DUMMY_WEBP).unwrap();
+    }
+    fs::write(
+        fuji.join("index.md"),
+        "!gallery A, B, C\n\n!pair C, D\n\n!compare D, E\n",
+    )
+    .unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &[]);
+    let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    assert!(group.contains(r#"<div class="row g-2 mb-3 image-gallery"#));
+    assert!(group.contains(r#"<div class="row g-2 mb-3 image-pair"#));
+    assert!(group.contains(r#"<div class="image-compare mb-3" id="d""#));
+    assert!(group.contains(r#"src="../thumbnails/large/2021-01-01-fuji-e.webp" alt="E""#));
+
+    // All images count as referenced, and unknown images are still reported.
+    fs::write(fuji.join("index.md"), "!gallery A, B, C\n\n!pair D, F\n").unwrap();
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unknown images in markdown file") && stderr.contains("\"F\""),
+        "{}",
+        stderr
+    );
+    fs::write(fuji.join("index.md"), "!gallery A, B, C, D\n").unwrap();
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("missing from the markdown file"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("\"E\""), "{}", stderr);
+
+    fs::write(
+        fuji.join("index.md"),
+        "!gallery A, B, C\n\n!compare D, E, D\n",
+    )
+    .unwrap();
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Wrong number of images in \"!compare D, E, D\""),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_unreferenced_images() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    for image in ["Summit", "Valley"] {
+        fs::write(fuji.join(image.to_owned() + ".webp"), DUMMY_WEBP).unwrap();
+    }
+    fs::write(fuji.join("index.md"), "!image Valley\n").unwrap();
+
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &[]);
+    assert_eq!(output.status.code(), Some(4));
+
+    let output = run_main_output(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--unreferenced_images=warn", "--languages=en,de"],
+    );
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Warning: Images not referenced in the markdown file")
+            && stderr.contains("\"Summit\""),
+        "{}",
+        stderr
+    );
+    // Both languages use the same markdown file, so the warning appears once.
+    assert_eq!(stderr.matches("\"Summit\"").count(), 1, "{}", stderr);
+    let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    assert!(!group.contains("2021-01-01-fuji-summit.webp"));
+
+    // The front matter overrides the command line.
+    fs::write(
+        fuji.join("index.md"),
+        "---\nunreferenced_images: append\n---\n!image Valley\n",
+    )
+    .unwrap();
+    run_main(inputdir, outputdir, "Title", "Footer", &[]);
+    let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    let valley = group.find("2021-01-01-fuji-valley.webp").unwrap();
+    let summit = group
+        .find(r#"<div class="row g-2 mb-3 image-gallery"#)
+        .unwrap();
+    assert!(valley < summit);
+    assert!(group[summit..].contains("2021-01-01-fuji-summit.webp"));
+}
+
+#[test]
+fn test_markdown_links() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    for group in ["2021-01-01 Fuji", "2021-01-02 Zoo"] {
+        fs::create_dir_all(inputdir.join(group)).unwrap();
+        fs::write(inputdir.join(group).join("Summit.webp"), DUMMY_WEBP).unwrap();
+        fs::write(
+            inputdir.join(group).join("index.md"),
+            "!image Summit\n\n## Day 2\n",
+        )
+        .unwrap();
+    }
+    fs::write(
+        inputdir.join("2021-01-01 Fuji/index.md"),
+        "!image Summit\n\n[next](<group:2021-01-02 Zoo#day-2>), [this](image:Summit), \
+         [that](<image:2021-01-02 Zoo/Summit>)\n",
+    )
+    .unwrap();
+    fs::create_dir_all(inputdir.join("pages")).unwrap();
+    fs::write(
+        inputdir.join("pages/About.md"),
+        "[Zoo](<group:2021-01-02 Zoo>)\n",
+    )
+    .unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &[]);
+    let fuji = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    assert!(fuji.contains(r##"<a href="../html/2021-01-02-zoo.html#day-2">next</a>"##));
+    assert!(fuji.contains(r##"<a href="#summit">this</a>"##));
+    // Images in other image groups link to the image on the page of the group.
+    assert!(fuji.contains(r##"<a href="../html/2021-01-02-zoo.html#summit">that</a>"##));
+    let about = fs::read_to_string(outputdir.join("pages/about.html")).unwrap();
+    assert!(about.contains(r#"<a href="../html/2021-01-02-zoo.html">Zoo</a>"#));
+
+    fs::write(
+        inputdir.join("pages/About.md"),
+        "[Zoo](group:Zoo) [Valley](image:Valley) [typo](<group:2021-01-02 Zoo#dya-2>)\n",
+    )
+    .unwrap();
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "Broken links: unknown image group \"Zoo\", image \"Valley\" without an image group"
+        ) && stderr.contains("unknown anchor \"2021-01-02 Zoo#dya-2\""),
+        "{}",
+        stderr
+    );
+
+    // An image left out of the page is not a link target, in the same and in other groups.
+    fs::remove_file(inputdir.join("pages/About.md")).unwrap();
+    fs::write(inputdir.join("2021-01-01 Fuji/Valley.webp"), DUMMY_WEBP).unwrap();
+    fs::write(inputdir.join("2021-01-02 Zoo/Crater.webp"), DUMMY_WEBP).unwrap();
+    fs::write(
+        inputdir.join("2021-01-01 Fuji/index.md"),
+        "!image Summit\n\n[valley](image:Valley) [crater](<image:2021-01-02 Zoo/Crater>)\n",
+    )
+    .unwrap();
+    let output = run_main_output(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--unreferenced_images=warn"],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Broken links: image \"2021-01-02 Zoo/Crater\" is not on the page")
+            && stderr.contains("image \"Valley\" is not on the page"),
+        "{}",
+        stderr
+    );
+    run_main(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--unreferenced_images=append"],
+    );
+}
+
+#[test]
+fn test_languages() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let fuji = inputdir.join("2021-01-02 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    fs::write(fuji.join("Summit.webp"), DUMMY_WEBP).unwrap();
+    fs::write(
+        fuji.join("index.md"),
+        "---\ntitle.de: Fudschi\n---\nA hike.\n\n!image Summit\n",
+    )
+    .unwrap();
+    fs::write(
+        fuji.join("index.de.md"),
+        "Eine Wanderung.\n\n!image Summit\n",
+    )
+    .unwrap();
+    fs::create_dir_all(inputdir.join("pages")).unwrap();
+    fs::write(inputdir.join("pages/About.md"), "Hello\n").unwrap();
+
+    run_main(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--languages=en,de"],
+    );
+
+    // The default language stays at the top level.
+    let group = fs::read_to_string(outputdir.join("html/2021-01-02-fuji.html")).unwrap();
+    assert!(group.contains(r#"<html lang="en">"#));
+    assert!(group.contains("<p>A hike.</p>"));
+    assert!(group.contains(r#"(<time datetime="2021-01-02">2021-01-02</time>)"#));
+    assert!(group.contains(
+        r#"<link rel="alternate" hreflang="de" href="../de/html/2021-01-02-fuji.html">"#
+    ));
+
+    // The other languages get a parallel tree with translated content, titles, and dates.
+    let group = fs::read_to_string(outputdir.join("de/html/2021-01-02-fuji.html")).unwrap();
+    assert!(group.contains(r#"<html lang="de">"#));
+    assert!(group.contains("<p>Eine Wanderung.</p>"));
+    assert!(group.contains(r#"Fudschi (<time datetime="2021-01-02">02.01.2021</time>)"#));
+    assert!(group.contains(
+        r#"<link rel="alternate" hreflang="en" href="../../html/2021-01-02-fuji.html">"#
+    ));
+    assert!(group.contains(r#"<a href="../../html/2021-01-02-fuji.html" hreflang="en" >en</a>"#));
+    // Images and static assets are shared.
+    assert!(group.contains(r#"href="../../css/style.css""#));
+    assert!(group.contains(r#"src="../../thumbnails/"#));
+
+    let overview = fs::read_to_string(outputdir.join("de/index.html")).unwrap();
+    assert!(overview.contains(r#"placeholder="Suche""#));
+    assert!(overview.contains(r#"href="../css/style.css""#));
+    assert!(overview.contains("Fudschi"));
+    assert!(outputdir.join("de/pages/about.html").is_file());
+    assert_eq!(
+        embedded_json(&overview, "search-index")[0]["title"],
+        "Fudschi"
+    );
+    assert!(!outputdir.join("de/css").exists());
+
+    // Without languages, there is only one tree in English.
+    fs::remove_dir_all(outputdir).unwrap();
+    run_main(inputdir, outputdir, "Title", "Footer", &[]);
+    let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    assert!(overview.contains(r#"<html lang="en-US">"#));
+    assert!(!overview.contains("hreflang"));
+    assert!(!outputdir.join("de").exists());
+}
+
+#[test]
+fn test_date_format() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let fuji = inputdir.join("2021-01-01..2021-01-03 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    fs::write(fuji.join("Summit.webp"), DUMMY_WEBP).unwrap();
+
+    run_main(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &["--date_format=long"],
+    );
+
+    // The datetime attribute keeps the ISO date for machines, one per day of a range.
+    let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    assert!(overview.contains(
+        r#"<time datetime="2021-01-01">1 January 2021</time> – <time datetime="2021-01-03">3 January 2021</time></small>"#
+    ));
+
+    run_main(inputdir, outputdir, "Title", "Footer", &["--format=json"]);
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(outputdir.join("gallery.json")).unwrap()).unwrap();
+    let group = &json["image_groups"][0];
+    assert_eq!(group["date"], "2021-01-01 – 2021-01-03");
+    assert_eq!(group["datetime"], "2021-01-01");
+    assert_eq!(group["datetime_end"], "2021-01-03");
+}
+
+/// The JSON embedded in an HTML page in a `<script>` element with the given ID.
+fn embedded_json(html: &str, id: &str) -> serde_json::Value {
+    let (_, blob) = html
+        .split_once(&format!(r#"<script type="application/json" id="{}">"#, id))
+        .unwrap();
+    let (blob, _) = blob.split_once("</script>").unwrap();
+    serde_json::from_str(blob).unwrap()
+}
+
+#[test]
+fn test_lightbox_json() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let group = inputdir.join(r#"2021-01-01 Say "hi" \ 'bye'"#);
+    fs::create_dir_all(&group).unwrap();
+    fs::write(group.join("Summit.webp"), DUMMY_WEBP).unwrap();
+    fs::write(
+        group.join("index.md"),
+        "!image Summit | A \"quote\", a \\ and \\</script\\>\n",
+    )
+    .unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &[]);
+
+    let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    assert_eq!(
+        embedded_json(&overview, "lightbox-images"),
+        serde_json::json!([{
+            "url": "img/2021-01-01-say-hi-bye-summit.webp",
+            "title": "2021-01-01\u{2003}Say \"hi\" \\ 'bye', A \"quote\", a \\ and </script>",
+        }])
+    );
+}
+
+#[test]
+fn test_image_group_lightbox() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    for image in ["Lake", "Summit", "Valley"] {
+        fs::write(fuji.join(image.to_owned() + ".webp"), DUMMY_WEBP).unwrap();
+    }
+    fs::write(
+        fuji.join("index.md"),
+        "!image Valley\n\n!image Lake | Morning\n\n!image Summit\n",
+    )
+    .unwrap();
+
+    run_main(inputdir, outputdir, "Title", "Footer", &[]);
+
+    // The lightbox follows the order of the markdown file and uses the links of the page.
+    let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    let images = embedded_json(&group, "lightbox-images");
+    let urls = images
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["url"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        urls,
+        [
+            "../img/2021-01-01-fuji-valley.webp",
+            "../img/2021-01-01-fuji-lake.webp",
+            "../img/2021-01-01-fuji-summit.webp",
+        ]
+    );
+    assert_eq!(images[1]["title"], "2021-01-01\u{2003}Fuji, Morning");
+    for url in urls {
+        assert!(group.contains(&format!(r#"<a href="{}">"#, url)), "{}", url);
+    }
+    assert!(group.contains(r#"<script src="../js/lightbox.js"></script>"#));
+    assert!(outputdir.join("js/lightbox.js").exists());
+}
+
+#[test]
+fn test_theme() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+    let theme = &tempdir.join("theme");
+
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    fs::write(fuji.join("Summit.webp"), DUMMY_WEBP).unwrap();
+    fs::write(fuji.join("index.md"), "!image Summit\n").unwrap();
+    fs::create_dir_all(theme.join("partials")).unwrap();
+    fs::write(
+        theme.join("partials/footer.handlebars"),
+        r#"<footer class="custom">{{{footer}}}</footer>"#,
+    )
+    .unwrap();
+    fs::write(
+        theme.join("image_group.handlebars"),
+        r#"{{> banner}}<link href="{{relative_url root "css/style.css"}}"> {{image_count}} {{pluralize image_count "photo" "photos"}}{{> footer}}"#,
+    )
+    .unwrap();
+    fs::write(
+        theme.join("partials/banner.handlebars"),
+        "<h1>{{title}}</h1>",
+    )
+    .unwrap();
+
+    let theme_arg = format!("--theme={}", theme.to_string_lossy());
+    run_main(inputdir, outputdir, "Title", "Footer", &[&theme_arg]);
+
+    // Templates without a replacement keep using the built-in partials.
+    let overview = fs::read_to_string(outputdir.join("index.html")).unwrap();
+    assert!(overview.contains(r#"<footer class="custom">Footer</footer>"#));
+    assert!(overview.contains(r#"<link href="css/style.css" rel="stylesheet">"#));
+    let group = fs::read_to_string(outputdir.join("html/2021-01-01-fuji.html")).unwrap();
+    assert_eq!(
+        group,
+        r#"<h1>Fuji</h1><link href="../css/style.css"> 1 photo<footer class="custom">Footer</footer>"#
+    );
+
+    // Translations of the theme add languages and override built-in strings.
+    fs::create_dir_all(theme.join("translations")).unwrap();
+    fs::write(
+        theme.join("translations/fr.json"),
+        r#"{"strings": {"Search": "Recherche"}}"#,
+    )
+    .unwrap();
+    fs::write(
+        theme.join("translations/de.json"),
+        r#"{"strings": {"Search": "Suchen"}}"#,
+    )
+    .unwrap();
+    run_main(
+        inputdir,
+        outputdir,
+        "Title",
+        "Footer",
+        &[&theme_arg, "--languages=en,fr,de"],
+    );
+    let overview = fs::read_to_string(outputdir.join("fr/index.html")).unwrap();
+    assert!(overview.contains(r#"placeholder="Recherche""#));
+    let overview = fs::read_to_string(outputdir.join("de/index.html")).unwrap();
+    assert!(overview.contains(r#"placeholder="Suchen""#));
+    assert!(overview.contains(r#"aria-label="Sprachen""#));
+
+    fs::write(theme.join("translations/fr.json"), r#"{"string": {}}"#).unwrap();
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &[&theme_arg]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("fr.json"), "{}", stderr);
+    fs::remove_file(theme.join("translations/fr.json")).unwrap();
+
+    fs::write(theme.join("tag.handlebars"), "{{#if}}").unwrap();
+    let output = run_main_output(inputdir, outputdir, "Title", "Footer", &[&theme_arg]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("tag.handlebars"), "{}", stderr);
+}
@@ -0,0 +1,73 @@
+//! Tests for the library API.
+use gallery::{Category, Config, ErrorMode, RunMode};
+use std::fs;
+
+#[test]
+fn test_read_gallery() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let inputdir = tempdir_raw.path();
+    fs::create_dir(inputdir.join("2021-01-01 Fuji, Japan")).unwrap();
+    fs::write(inputdir.join("2021-01-01 Fuji, Japan/Summit.webp"), "").unwrap();
+    fs::create_dir(inputdir.join("Undated")).unwrap();
+
+    let (gallery, diagnostics) = gallery::read_gallery(inputdir, false, &[]).unwrap();
+    assert_eq!(gallery.image_groups.len(), 1);
+    assert_eq!(gallery.image_groups[0].title, "Fuji, Japan");
+    assert_eq!(gallery.image_groups[0].images[0].name, "Summit");
+    assert_eq!(diagnostics.skipped.len(), 1);
+
+    assert!(matches!(
+        gallery::read_gallery(inputdir, true, &[]),
+        Err(gallery::Error::SkippedInputEntries { .. })
+    ));
+}
+
+#[test]
+fn test_build_dry_run() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let outputdir = &tempdir.join("output");
+    fs::create_dir(inputdir).unwrap();
+
+    let config = Config::builder(outputdir)
+        .page_title("Title")
+        .run_mode(RunMode::DryRun)
+        .build();
+    let diagnostics = gallery::build(inputdir, true, &config).unwrap();
+    assert!(diagnostics.skipped.is_empty());
+    assert!(!outputdir.exists());
+}
+
+#[test]
+fn test_collected_errors() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let tempdir = tempdir_raw.path();
+    let inputdir = &tempdir.join("input");
+    let fuji = inputdir.join("2021-01-01 Fuji");
+    fs::create_dir_all(&fuji).unwrap();
+    fs::write(fuji.join("Summit.webp"), include_bytes!("dummy.webp")).unwrap();
+    fs::write(fuji.join("index.md"), "!image Summit\n!image Crater\n").unwrap();
+
+    let config = Config::builder(tempdir.join("output"))
+        .run_mode(RunMode::DryRun)
+        .error_mode(ErrorMode::KeepGoing)
+        .build();
+    let Err(gallery::Error::Collected(errors)) = gallery::build(inputdir, false, &config) else {
+        panic!("expected collected errors");
+    };
+    assert!(!errors.is_empty());
+    for (category, e) in &errors {
+        assert!(matches!(category, Category::Pages), "{:?}", category);
+        assert!(matches!(e, gallery::Error::UnknownMarkdownImages { .. }));
+    }
+}
+
+#[test]
+fn test_missing_input_dir() {
+    let tempdir_raw = tempfile::tempdir().unwrap();
+    let inputdir = &tempdir_raw.path().join("missing");
+    let err = gallery::read_gallery(inputdir, false, &[]).unwrap_err();
+    assert!(matches!(err, gallery::Error::MissingInputDirectory { .. }));
+    assert_eq!(err.path(), Some(inputdir.as_path()));
+}